@@ -0,0 +1,138 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod browser_module {
+    use std::error::Error;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{channel, Receiver, TryRecvError};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use rfd::AsyncFileDialog;
+
+    /// A native dialog closed sooner than this without a file could not be shown (no desktop portal
+    /// nor zenity on Linux): rfd reports it like a cancel, nobody cancels that fast
+    const NATIVE_DIALOG_MIN_OPEN: Duration = Duration::from_millis(300);
+
+    /// What the chosen file is used for
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum BrowserMode {
+        Save,
+        Open,
+    }
+
+    /// State of the in-app file browser, used when the native file dialog is not available
+    pub struct FileBrowser {
+        pub mode: BrowserMode,
+        pub folder: PathBuf,
+        /// name typed by the user, relative to folder
+        pub file_name: String,
+        /// only files with these extensions are listed, all of them when empty
+        pub extensions: Vec<String>,
+        pub folders: Vec<PathBuf>,
+        pub files: Vec<PathBuf>,
+    }
+
+    impl FileBrowser {
+        pub fn new(mode: BrowserMode, folder: &Path, file_name: &str, extensions: Vec<String>) -> Result<FileBrowser, Box<dyn Error>> {
+            let mut browser = FileBrowser {
+                mode,
+                folder: PathBuf::new(),
+                file_name: file_name.to_string(),
+                extensions,
+                folders: Vec::new(),
+                files: Vec::new(),
+            };
+            browser.open(folder)?;
+            Ok(browser)
+        }
+
+        /// Lists the content of folder, sub folders and files sorted by name; hidden entries are left out
+        pub fn open(&mut self, folder: &Path) -> Result<(), Box<dyn Error>> {
+            let folder = folder.canonicalize()?;
+            let mut folders = Vec::new();
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(&folder)? {
+                let path = entry?.path();
+                if path.file_name().map(|name| name.to_string_lossy().starts_with('.')).unwrap_or(true) {
+                    continue;
+                }
+                if path.is_dir() {
+                    folders.push(path);
+                } else if self.accepts(&path) {
+                    files.push(path);
+                }
+            }
+            folders.sort();
+            files.sort();
+            self.folder = folder;
+            self.folders = folders;
+            self.files = files;
+            Ok(())
+        }
+
+        pub fn refresh(&mut self) -> Result<(), Box<dyn Error>> {
+            let folder = self.folder.clone();
+            self.open(&folder)
+        }
+
+        pub fn up(&mut self) -> Result<(), Box<dyn Error>> {
+            match self.folder.parent().map(Path::to_path_buf) {
+                Some(parent) => self.open(&parent),
+                None => Ok(()),
+            }
+        }
+
+        fn accepts(&self, path: &Path) -> bool {
+            let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+            self.extensions.is_empty() || self.extensions.contains(&extension)
+        }
+
+        /// Path of the selected file
+        pub fn path(&self) -> PathBuf {
+            self.folder.join(self.file_name.trim())
+        }
+    }
+
+    /// Outcome of a native file dialog
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum DialogResult {
+        Picked(PathBuf),
+        Cancelled,
+        /// the dialog could not be shown, the in-app browser has to be used instead
+        Unavailable,
+    }
+
+    /// A native file dialog waited for on a worker thread, so that the UI keeps being drawn while it is open
+    pub struct NativeDialog {
+        pub mode: BrowserMode,
+        receiver: Receiver<(Option<PathBuf>, Duration)>,
+    }
+
+    impl NativeDialog {
+        /// Shows dialog to pick a file to save or to open, notify is called once it is closed
+        pub fn show<F>(mode: BrowserMode, dialog: AsyncFileDialog, notify: F) -> NativeDialog
+            where F: Fn() + Send + 'static {
+            let (sender, receiver) = channel();
+            thread::spawn(move || {
+                let opened = Instant::now();
+                let file = match mode {
+                    BrowserMode::Save => pollster::block_on(dialog.save_file()),
+                    BrowserMode::Open => pollster::block_on(dialog.pick_file()),
+                };
+                // the receiver may be gone already, nothing to report in that case
+                let _ = sender.send((file.map(|file| file.path().to_path_buf()), opened.elapsed()));
+                notify();
+            });
+            NativeDialog { mode, receiver }
+        }
+
+        /// Returns the outcome once the dialog is closed, None while it is still open
+        pub fn try_result(&self) -> Option<DialogResult> {
+            match self.receiver.try_recv() {
+                Ok((Some(path), _)) => Some(DialogResult::Picked(path)),
+                Ok((None, open_for)) if open_for < NATIVE_DIALOG_MIN_OPEN => Some(DialogResult::Unavailable),
+                Ok((None, _)) => Some(DialogResult::Cancelled),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(DialogResult::Unavailable),
+            }
+        }
+    }
+}
@@ -0,0 +1,583 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod capture_module {
+    use std::error::Error;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+    use std::thread;
+    use std::time::Duration;
+    use image::{imageops, DynamicImage, RgbaImage};
+    use screenshots::Screen;
+    use serde::{Deserialize, Serialize};
+    use thiserror::Error;
+    use crate::naming_module::naming_module::NameContext;
+    use crate::platform_module::platform_module::{self, CursorImage, WindowInfo};
+    use crate::screenshots_module::screenshot_module::{check_area, Screenshot};
+
+    #[derive(Error, Debug)]
+    pub enum CaptureError {
+        #[error("display {0} not found")]
+        DisplayNotFound(usize),
+        #[error("no png images found in {0}")]
+        NoImages(String),
+        #[error("captured buffer has the wrong size")]
+        BufferSize,
+        #[error("no display available")]
+        NoDisplays,
+        #[error("no display contains the point ({0},{1})")]
+        NoDisplayAt(i32, i32),
+        #[error("window {0} is not visible in the capture")]
+        WindowNotFound(u32),
+    }
+
+    /// Screen index used to ask for the whole virtual desktop instead of a single monitor
+    pub const ALL_SCREENS: usize = usize::MAX;
+
+    /// Position and size of a monitor as seen by a capture backend
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Monitor {
+        pub index: usize,
+        pub x: i32,
+        pub y: i32,
+        pub width: u32,
+        pub height: u32,
+        pub scale_factor: f32,
+        pub is_primary: bool,
+    }
+
+    impl Monitor {
+        /// Size in physical pixels, width and height being logical units
+        pub fn physical_size(&self) -> (u32, u32) {
+            ((self.width as f32 * self.scale_factor).round() as u32, (self.height as f32 * self.scale_factor).round() as u32)
+        }
+    }
+
+    /// A captured image with the number of image pixels per logical unit it was taken at
+    pub struct Capture {
+        pub image: DynamicImage,
+        pub scale_factor: f32,
+    }
+
+    /// Rectangle of a screen in logical units relative to its top left corner, together with the
+    /// scale factor of the capture it was selected on
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct CaptureRegion {
+        pub screen: usize,
+        pub x: i32,
+        pub y: i32,
+        pub width: u32,
+        pub height: u32,
+        pub scale_factor: f32,
+    }
+
+    impl CaptureRegion {
+        /// Converts a rectangle in image pixels of a capture taken at scale_factor to logical units
+        pub fn from_pixels(screen: usize, area: (u32, u32, u32, u32), scale_factor: f32) -> CaptureRegion {
+            let scale = if scale_factor > 0.0 { scale_factor } else { 1.0 };
+            CaptureRegion {
+                screen,
+                x: (area.0 as f32 / scale).round() as i32,
+                y: (area.1 as f32 / scale).round() as i32,
+                width: ((area.2 as f32 / scale).round() as u32).max(1),
+                height: ((area.3 as f32 / scale).round() as u32).max(1),
+                scale_factor: scale,
+            }
+        }
+
+        /// (x, y, width, height) in logical units, as taken by capture_rect
+        pub fn area(&self) -> (i32, i32, u32, u32) {
+            (self.x, self.y, self.width, self.height)
+        }
+
+        /// (x, y, width, height) in pixels of a capture taken at the region scale factor
+        pub fn pixel_area(&self) -> (u32, u32, u32, u32) {
+            let scale = |v: f32| (v * self.scale_factor).round() as u32;
+            (scale(self.x.max(0) as f32), scale(self.y.max(0) as f32), scale(self.width as f32), scale(self.height as f32))
+        }
+    }
+
+    /// Pixels per logical unit of an image covering logical_width units, on mixed-DPI setups
+    /// this is the real density of the capture whatever scale factor the system reports
+    pub fn pixel_density(image: &DynamicImage, logical_width: u32) -> f32 {
+        image.width() as f32 / logical_width.max(1) as f32
+    }
+
+    /// A backend able to list the available monitors and grab their content
+    pub trait CaptureSource: Send + Sync {
+        fn displays(&self) -> Result<Vec<Monitor>, Box<dyn Error>>;
+
+        fn capture_display(&self, display: &Monitor) -> Result<DynamicImage, Box<dyn Error>>;
+
+        /// Captures the rectangle (x, y, width, height) given in coordinates relative to the display
+        fn capture_rect(&self, display: &Monitor, x: i32, y: i32, width: u32, height: u32) -> Result<DynamicImage, Box<dyn Error>>;
+
+        /// Position of the mouse pointer in the same coordinates as the displays
+        fn cursor_position(&self) -> Result<(i32, i32), Box<dyn Error>>;
+
+        /// Image and position of the mouse pointer, the bundled arrow is used by default
+        fn cursor_image(&self) -> Result<CursorImage, Box<dyn Error>> {
+            Ok(platform_module::bundled_cursor_image(self.cursor_position()?))
+        }
+
+        /// Top level windows from bottom to top, backends without windows return none
+        fn windows(&self) -> Result<Vec<WindowInfo>, Box<dyn Error>> {
+            Ok(Vec::new())
+        }
+
+        /// Returns the display containing the point (x, y)
+        fn display_at(&self, x: i32, y: i32) -> Result<Monitor, Box<dyn Error>> {
+            let found = self.displays()?.into_iter().find(|d| {
+                x >= d.x && y >= d.y && x < d.x + d.width as i32 && y < d.y + d.height as i32
+            });
+            match found {
+                Some(display) => Ok(display),
+                None => Err(Box::new(CaptureError::NoDisplayAt(x, y))),
+            }
+        }
+
+        fn display(&self, index: usize) -> Result<Monitor, Box<dyn Error>> {
+            match self.displays()?.into_iter().find(|d| d.index == index) {
+                Some(display) => Ok(display),
+                None => Err(Box::new(CaptureError::DisplayNotFound(index))),
+            }
+        }
+
+        /// Captures every display and composites them into one image using their desktop offsets
+        fn capture_desktop(&self) -> Result<DynamicImage, Box<dyn Error>> {
+            let mut captures = Vec::new();
+            for display in self.displays()? {
+                let image = self.capture_display(&display)?;
+                captures.push((display, image));
+            }
+            compose_desktop(&captures)
+        }
+    }
+
+    /// Desktop coordinates of the top left corner of a capture of screen (ALL_SCREENS included)
+    pub fn capture_origin(source: &dyn CaptureSource, screen: usize) -> Result<(i32, i32), Box<dyn Error>> {
+        if screen == ALL_SCREENS {
+            let displays = source.displays()?;
+            let min_x = displays.iter().map(|d| d.x).min().unwrap_or(0);
+            let min_y = displays.iter().map(|d| d.y).min().unwrap_or(0);
+            return Ok((min_x, min_y));
+        }
+        let display = source.display(screen)?;
+        Ok((display.x, display.y))
+    }
+
+    /// Rectangle (x, y, width, height) covered by a window in the pixels of a capture of size
+    /// (width, height, scale_factor) whose top left corner is at origin, clipped to the capture;
+    /// None when the window is not in the capture
+    pub fn window_pixel_area(window: &WindowInfo, decorations: bool, origin: (i32, i32), size: (u32, u32, f32)) -> Option<(u32, u32, u32, u32)> {
+        let (x, y, width, height) = window.area(decorations);
+        let (capture_width, capture_height, scale) = size;
+        let left = (((x - origin.0) as f32 * scale).round() as i64).max(0);
+        let top = (((y - origin.1) as f32 * scale).round() as i64).max(0);
+        let right = (((x - origin.0) as f32 + width as f32) * scale).round().min(capture_width as f32) as i64;
+        let bottom = (((y - origin.1) as f32 + height as f32) * scale).round().min(capture_height as f32) as i64;
+        if right <= left || bottom <= top {
+            return None;
+        }
+        Some((left as u32, top as u32, (right - left) as u32, (bottom - top) as u32))
+    }
+
+    /// Crops a capture to the frame of a window, with or without its decorations
+    pub fn crop_to_window(capture: &Capture, window: &WindowInfo, decorations: bool, origin: (i32, i32)) -> Result<Capture, Box<dyn Error>> {
+        match window_pixel_area(window, decorations, origin, (capture.image.width(), capture.image.height(), capture.scale_factor)) {
+            Some((x, y, width, height)) => Ok(Capture {
+                image: capture.image.crop_imm(x, y, width, height),
+                scale_factor: capture.scale_factor,
+            }),
+            None => Err(Box::new(CaptureError::WindowNotFound(window.id))),
+        }
+    }
+
+    /// Captures a display, or the whole desktop for ALL_SCREENS, drawing the mouse pointer on top when with_cursor is set
+    pub fn capture_screen(source: &dyn CaptureSource, screen: usize, with_cursor: bool) -> Result<Capture, Box<dyn Error>> {
+        let origin = capture_origin(source, screen)?;
+        let (mut image, width) = if screen == ALL_SCREENS {
+            let max_x = source.displays()?.iter().map(|d| d.x + d.width as i32).max().unwrap_or(0);
+            (source.capture_desktop()?, (max_x - origin.0) as u32)
+        } else {
+            let display = source.display(screen)?;
+            (source.capture_display(&display)?, display.width)
+        };
+        let scale_factor = pixel_density(&image, width);
+        if with_cursor {
+            // a pointer that cannot be read is simply left out of the capture
+            if let Ok(cursor) = source.cursor_image() {
+                draw_cursor(&mut image, &cursor, origin, scale_factor);
+            }
+        }
+        Ok(Capture { image, scale_factor })
+    }
+
+    /// Captures a region of its screen, drawing the mouse pointer on top when with_cursor is set
+    pub fn capture_region(source: &dyn CaptureSource, region: &CaptureRegion, with_cursor: bool) -> Result<Capture, Box<dyn Error>> {
+        let display = source.display(region.screen)?;
+        let (x, y, width, height) = region.area();
+        let mut capture = Screenshot::capture_area(source, &display, x, y, width, height)?.into_capture();
+        if with_cursor {
+            if let Ok(cursor) = source.cursor_image() {
+                draw_cursor(&mut capture.image, &cursor, (display.x + x, display.y + y), capture.scale_factor);
+            }
+        }
+        Ok(capture)
+    }
+
+    /// Details of a capture of screen for the file name template and the metadata, the screen is left out
+    /// for captures of all screens
+    pub fn name_context(source: &dyn CaptureSource, screen: usize, preset: Option<String>) -> NameContext {
+        if screen == ALL_SCREENS {
+            return NameContext::new(None, preset);
+        }
+        let mut context = NameContext::new(Some(screen), preset);
+        context.screen_size = source.displays().unwrap_or_default().iter().find(|monitor| monitor.index == screen).map(Monitor::physical_size);
+        context
+    }
+
+    pub fn capture_image(source: &dyn CaptureSource, timer: Duration, screen: usize, with_cursor: bool) -> Result<Capture, Box<dyn Error>> {
+        std::thread::sleep(timer);
+        capture_screen(source, screen, with_cursor)
+    }
+
+    pub fn take_screenshot(source: &dyn CaptureSource, timer: Duration, screen: usize, with_cursor: bool) -> Result<Screenshot, Box<dyn Error>> {
+        //screenshot after delay
+        let capture = capture_image(source, timer, screen, with_cursor)?;
+        Ok(Screenshot::from_capture(capture))
+    }
+
+    /// Blends the pointer on an image whose top left corner is at origin in desktop coordinates,
+    /// scale being the number of image pixels per desktop unit
+    pub fn draw_cursor(image: &mut DynamicImage, cursor: &CursorImage, origin: (i32, i32), scale: f32) {
+        let x = ((cursor.position.0 - origin.0) as f32 * scale) as i64 - (cursor.hotspot.0 as f32 * scale) as i64;
+        let y = ((cursor.position.1 - origin.1) as f32 * scale) as i64 - (cursor.hotspot.1 as f32 * scale) as i64;
+        if (scale - 1.0).abs() < f32::EPSILON {
+            imageops::overlay(image, &cursor.image, x, y);
+        } else {
+            let width = ((cursor.image.width() as f32 * scale).round() as u32).max(1);
+            let height = ((cursor.image.height() as f32 * scale).round() as u32).max(1);
+            let resized = imageops::resize(&cursor.image, width, height, imageops::FilterType::Triangle);
+            imageops::overlay(image, &resized, x, y);
+        }
+    }
+
+    /// Places each capture at its monitor offset on a single canvas. Monitors with a lower pixel
+    /// density than the densest one are upscaled so that every monitor keeps its desktop size, and
+    /// gaps between monitors are left transparent
+    pub fn compose_desktop(captures: &[(Monitor, DynamicImage)]) -> Result<DynamicImage, Box<dyn Error>> {
+        if captures.is_empty() {
+            return Err(Box::new(CaptureError::NoDisplays));
+        }
+        let min_x = captures.iter().map(|(m, _)| m.x).min().unwrap();
+        let min_y = captures.iter().map(|(m, _)| m.y).min().unwrap();
+        let max_x = captures.iter().map(|(m, _)| m.x + m.width as i32).max().unwrap();
+        let max_y = captures.iter().map(|(m, _)| m.y + m.height as i32).max().unwrap();
+        // pixels per desktop unit: the real density of each capture, whatever scale factor is reported
+        let scale = captures.iter()
+            .map(|(m, image)| pixel_density(image, m.width))
+            .fold(1.0, f32::max);
+
+        let canvas_width = ((max_x - min_x) as f32 * scale).round() as u32;
+        let canvas_height = ((max_y - min_y) as f32 * scale).round() as u32;
+        let mut canvas = RgbaImage::new(canvas_width, canvas_height);
+        for (monitor, image) in captures {
+            let width = (monitor.width as f32 * scale).round() as u32;
+            let height = (monitor.height as f32 * scale).round() as u32;
+            let x = ((monitor.x - min_x) as f32 * scale).round() as i64;
+            let y = ((monitor.y - min_y) as f32 * scale).round() as i64;
+            if image.width() == width && image.height() == height {
+                imageops::replace(&mut canvas, &image.to_rgba8(), x, y);
+            } else {
+                let resized = image.resize_exact(width, height, imageops::FilterType::Triangle);
+                imageops::replace(&mut canvas, &resized.to_rgba8(), x, y);
+            }
+        }
+        Ok(DynamicImage::from(canvas))
+    }
+
+    /// Real monitors, captured through the screenshots crate
+    pub struct ScreensCapture;
+
+    impl ScreensCapture {
+        fn screen(display: &Monitor) -> Result<Screen, Box<dyn Error>> {
+            let screens = Screen::all()?;
+            match screens.get(display.index) {
+                Some(screen) => Ok(*screen),
+                None => Err(Box::new(CaptureError::DisplayNotFound(display.index))),
+            }
+        }
+
+        fn to_dynamic_image(image: screenshots::Image) -> Result<DynamicImage, Box<dyn Error>> {
+            let width = image.width();
+            let height = image.height();
+            match RgbaImage::from_raw(width, height, image.rgba().to_owned()) {
+                Some(rgba_image) => Ok(DynamicImage::from(rgba_image)),
+                None => Err(Box::new(CaptureError::BufferSize)),
+            }
+        }
+    }
+
+    impl CaptureSource for ScreensCapture {
+        fn displays(&self) -> Result<Vec<Monitor>, Box<dyn Error>> {
+            let screens = Screen::all()?;
+            Ok(screens.iter().enumerate().map(|(index, screen)| Monitor {
+                index,
+                x: screen.display_info.x,
+                y: screen.display_info.y,
+                width: screen.display_info.width,
+                height: screen.display_info.height,
+                scale_factor: screen.display_info.scale_factor,
+                is_primary: screen.display_info.is_primary,
+            }).collect())
+        }
+
+        fn capture_display(&self, display: &Monitor) -> Result<DynamicImage, Box<dyn Error>> {
+            let screen = Self::screen(display)?;
+            Self::to_dynamic_image(screen.capture()?)
+        }
+
+        fn capture_rect(&self, display: &Monitor, x: i32, y: i32, width: u32, height: u32) -> Result<DynamicImage, Box<dyn Error>> {
+            check_area(display, x, y, width, height)?;
+            let screen = Self::screen(display)?;
+            Self::to_dynamic_image(screen.capture_area(x, y, width, height)?)
+        }
+
+        fn cursor_position(&self) -> Result<(i32, i32), Box<dyn Error>> {
+            platform_module::cursor_position()
+        }
+
+        fn windows(&self) -> Result<Vec<WindowInfo>, Box<dyn Error>> {
+            platform_module::list_windows()
+        }
+
+        fn cursor_image(&self) -> Result<CursorImage, Box<dyn Error>> {
+            match platform_module::cursor_image() {
+                Ok(cursor) => Ok(cursor),
+                Err(_) => Ok(platform_module::bundled_cursor_image(self.cursor_position()?)),
+            }
+        }
+    }
+
+    /// Fake monitors served from png files: a single file is one monitor, a directory gives one
+    /// monitor per png (sorted by name) laid out from left to right
+    pub struct FileCapture {
+        files: Vec<PathBuf>,
+    }
+
+    impl FileCapture {
+        pub fn new(path: &Path) -> Result<FileCapture, Box<dyn Error>> {
+            let mut files = Vec::new();
+            if path.is_dir() {
+                for entry in std::fs::read_dir(path)? {
+                    let file = entry?.path();
+                    let is_png = file.extension().map(|ext| ext.eq_ignore_ascii_case("png")).unwrap_or(false);
+                    if file.is_file() && is_png {
+                        files.push(file);
+                    }
+                }
+                files.sort();
+            } else if path.is_file() {
+                files.push(path.to_path_buf());
+            }
+            if files.is_empty() {
+                return Err(Box::new(CaptureError::NoImages(path.display().to_string())));
+            }
+            Ok(FileCapture { files })
+        }
+
+        fn open(&self, display: &Monitor) -> Result<DynamicImage, Box<dyn Error>> {
+            match self.files.get(display.index) {
+                Some(file) => Ok(image::open(file)?),
+                None => Err(Box::new(CaptureError::DisplayNotFound(display.index))),
+            }
+        }
+    }
+
+    impl CaptureSource for FileCapture {
+        fn displays(&self) -> Result<Vec<Monitor>, Box<dyn Error>> {
+            let mut displays = Vec::new();
+            let mut x = 0;
+            for (index, file) in self.files.iter().enumerate() {
+                let (width, height) = image::image_dimensions(file)?;
+                displays.push(Monitor {
+                    index,
+                    x,
+                    y: 0,
+                    width,
+                    height,
+                    scale_factor: 1.0,
+                    is_primary: index == 0,
+                });
+                x += width as i32;
+            }
+            Ok(displays)
+        }
+
+        fn capture_display(&self, display: &Monitor) -> Result<DynamicImage, Box<dyn Error>> {
+            self.open(display)
+        }
+
+        fn capture_rect(&self, display: &Monitor, x: i32, y: i32, width: u32, height: u32) -> Result<DynamicImage, Box<dyn Error>> {
+            check_area(display, x, y, width, height)?;
+            let image = self.open(display)?;
+            Ok(image.crop_imm(x as u32, y as u32, width, height))
+        }
+
+        /// There is no real pointer, it always rests on the top left corner of the first file
+        fn cursor_position(&self) -> Result<(i32, i32), Box<dyn Error>> {
+            Ok((0, 0))
+        }
+    }
+
+    /// Messages ending the wait of a capture job before its delay
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Signal {
+        Start,
+        Cancel,
+    }
+
+    /// A capture running on a worker thread, the result is reported back over a channel
+    pub struct CaptureJob {
+        receiver: Receiver<Result<Capture, String>>,
+        signals: Sender<Signal>,
+    }
+
+    impl CaptureJob {
+        /// Captures the screen after waiting delay, notify is called once the result has been sent
+        pub fn spawn<F>(source: Arc<dyn CaptureSource>, screen: usize, with_cursor: bool, delay: Duration, notify: F) -> CaptureJob
+            where F: Fn() + Send + 'static {
+            CaptureJob::spawn_with(move || capture_screen(source.as_ref(), screen, with_cursor), delay, notify)
+        }
+
+        /// Captures only the given region after waiting delay
+        pub fn spawn_region<F>(source: Arc<dyn CaptureSource>, region: CaptureRegion, with_cursor: bool, delay: Duration, notify: F) -> CaptureJob
+            where F: Fn() + Send + 'static {
+            CaptureJob::spawn_with(move || capture_region(source.as_ref(), &region, with_cursor), delay, notify)
+        }
+
+        fn spawn_with<C, F>(capture: C, delay: Duration, notify: F) -> CaptureJob
+            where C: FnOnce() -> Result<Capture, Box<dyn Error>> + Send + 'static, F: Fn() + Send + 'static {
+            let (sender, receiver) = channel();
+            let (signals, received) = channel();
+            thread::spawn(move || {
+                // a start ends the wait early, a cancel or the job being dropped ends it without capturing
+                match received.recv_timeout(delay) {
+                    Ok(Signal::Start) | Err(RecvTimeoutError::Timeout) => {}
+                    Ok(Signal::Cancel) | Err(RecvTimeoutError::Disconnected) => return,
+                }
+                let result = capture();
+                if !received.try_iter().any(|signal| signal == Signal::Cancel) {
+                    // the receiver may be gone already, nothing to report in that case
+                    let _ = sender.send(result.map_err(|e| e.to_string()));
+                    notify();
+                }
+            });
+            CaptureJob { receiver, signals }
+        }
+
+        /// Returns the captured image once the worker is done, None while it is still running
+        pub fn try_result(&self) -> Option<Result<Capture, Box<dyn Error>>> {
+            match self.receiver.try_recv() {
+                Ok(result) => Some(result.map_err(|e| e.into())),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Err("capture worker stopped unexpectedly".into())),
+            }
+        }
+
+        /// Captures right away when the job is still waiting for its delay
+        pub fn start(&self) {
+            // the worker may be done already
+            let _ = self.signals.send(Signal::Start);
+        }
+
+        /// Stops the job, before capturing when it is still waiting for its delay
+        pub fn cancel(&self) {
+            let _ = self.signals.send(Signal::Cancel);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use image::Rgba;
+
+        /// Fresh folder in the system temp dir, one per test
+        fn temp_dir(name: &str) -> PathBuf {
+            let dir = std::env::temp_dir().join(format!("rusty_capture_{}_{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            dir
+        }
+
+        fn save_png(path: &Path, width: u32, height: u32, color: [u8; 4]) {
+            RgbaImage::from_pixel(width, height, Rgba(color)).save(path).unwrap();
+        }
+
+        #[test]
+        fn files_are_laid_out_left_to_right() {
+            let dir = temp_dir("layout");
+            save_png(&dir.join("0.png"), 4, 3, [255, 0, 0, 255]);
+            save_png(&dir.join("1.png"), 2, 5, [0, 0, 255, 255]);
+            let source = FileCapture::new(&dir).unwrap();
+            let displays = source.displays().unwrap();
+            assert_eq!(displays.iter().map(|d| (d.x, d.y, d.width, d.height)).collect::<Vec<_>>(), vec![(0, 0, 4, 3), (4, 0, 2, 5)]);
+            assert_eq!(source.capture_display(&displays[1]).unwrap().to_rgba8().get_pixel(1, 4), &Rgba([0, 0, 255, 255]));
+            assert!(source.display(2).is_err());
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn desktop_puts_files_side_by_side() {
+            let dir = temp_dir("desktop");
+            save_png(&dir.join("0.png"), 4, 3, [255, 0, 0, 255]);
+            save_png(&dir.join("1.png"), 2, 5, [0, 0, 255, 255]);
+            let desktop = FileCapture::new(&dir).unwrap().capture_desktop().unwrap().to_rgba8();
+            assert_eq!(desktop.dimensions(), (6, 5));
+            assert_eq!(desktop.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+            assert_eq!(desktop.get_pixel(3, 2), &Rgba([255, 0, 0, 255]));
+            assert_eq!(desktop.get_pixel(4, 0), &Rgba([0, 0, 255, 255]));
+            assert_eq!(desktop.get_pixel(5, 4), &Rgba([0, 0, 255, 255]));
+            // below the shorter monitor nothing was captured
+            assert_eq!(desktop.get_pixel(0, 4), &Rgba([0, 0, 0, 0]));
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn compose_desktop_handles_negative_offsets_and_density() {
+            let left = Monitor { index: 0, x: -2, y: 1, width: 2, height: 2, scale_factor: 1.0, is_primary: false };
+            let right = Monitor { index: 1, x: 0, y: 0, width: 2, height: 2, scale_factor: 2.0, is_primary: true };
+            let captures = vec![
+                (left, DynamicImage::from(RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255])))),
+                (right, DynamicImage::from(RgbaImage::from_pixel(4, 4, Rgba([0, 255, 0, 255])))),
+            ];
+            let desktop = compose_desktop(&captures).unwrap().to_rgba8();
+            // everything at the density of the 2x monitor
+            assert_eq!(desktop.dimensions(), (8, 6));
+            assert_eq!(desktop.get_pixel(0, 2), &Rgba([255, 0, 0, 255]));
+            assert_eq!(desktop.get_pixel(3, 5), &Rgba([255, 0, 0, 255]));
+            assert_eq!(desktop.get_pixel(4, 0), &Rgba([0, 255, 0, 255]));
+            assert_eq!(desktop.get_pixel(0, 0), &Rgba([0, 0, 0, 0]));
+            assert!(compose_desktop(&[]).is_err());
+        }
+
+        #[test]
+        fn capture_rect_crops_inside_the_display_only() {
+            let dir = temp_dir("rect");
+            let mut image = RgbaImage::from_pixel(8, 6, Rgba([0, 0, 0, 255]));
+            image.put_pixel(3, 2, Rgba([255, 255, 255, 255]));
+            image.save(dir.join("screen.png")).unwrap();
+            let source = FileCapture::new(&dir.join("screen.png")).unwrap();
+            let display = source.display(0).unwrap();
+
+            let area = source.capture_rect(&display, 3, 2, 4, 3).unwrap().to_rgba8();
+            assert_eq!(area.dimensions(), (4, 3));
+            assert_eq!(area.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+            assert!(source.capture_rect(&display, -1, 0, 2, 2).is_err());
+            assert!(source.capture_rect(&display, 5, 0, 4, 2).is_err());
+            assert!(source.capture_rect(&display, 0, 0, 0, 2).is_err());
+            assert!(FileCapture::new(&dir.join("missing")).is_err());
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,151 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod clipboard_module {
+    use std::borrow::Cow;
+    use std::error::Error;
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+    use arboard::{Clipboard, ImageData};
+    use image::{DynamicImage, ImageFormat, RgbaImage};
+    use serde::{Deserialize, Serialize};
+    use thiserror::Error;
+    use url::Url;
+    use crate::format_module::format_module::{encode_image, read_image, ExportOptions};
+
+    const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    #[derive(Error, Debug)]
+    pub enum ClipboardError {
+        #[error("{0} can not be written as a file URI")]
+        InvalidPath(String),
+        #[error("the clipboard does not contain an image")]
+        NoImage,
+    }
+
+    /// What a copy puts on the clipboard
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum ClipboardTarget {
+        /// the image itself, offered as PNG where the system supports it
+        Image,
+        /// the saved file, as a file list (text/uri-list, CF_HDROP) pasted by file managers and upload fields
+        File,
+        /// a base64 data:image/png URI as text
+        DataUri,
+        /// an <img> snippet as HTML, with a Markdown image as the plain text alternative
+        Markdown,
+    }
+
+    impl ClipboardTarget {
+        pub const ALL: [ClipboardTarget; 4] = [ClipboardTarget::Image, ClipboardTarget::File, ClipboardTarget::DataUri, ClipboardTarget::Markdown];
+
+        pub fn label(&self) -> &'static str {
+            match self {
+                ClipboardTarget::Image => "Image",
+                ClipboardTarget::File => "File",
+                ClipboardTarget::DataUri => "Data URI",
+                ClipboardTarget::Markdown => "Markdown / HTML",
+            }
+        }
+
+        /// Targets referencing a file need the screenshot saved first
+        pub fn needs_file(&self) -> bool {
+            matches!(self, ClipboardTarget::File | ClipboardTarget::Markdown)
+        }
+    }
+
+    pub fn copy_image(image: &DynamicImage) -> Result<(), Box<dyn Error>> {
+        let rgba = image.to_rgba8();
+        Clipboard::new()?.set_image(ImageData {
+            width: rgba.width() as usize,
+            height: rgba.height() as usize,
+            bytes: Cow::from(rgba.as_raw().as_slice()),
+        })?;
+        Ok(())
+    }
+
+    /// Image on the clipboard, or the first copied file when it is one (files copied in a file manager)
+    pub fn paste_image() -> Result<DynamicImage, Box<dyn Error>> {
+        let mut clipboard = Clipboard::new()?;
+        if let Ok(image) = clipboard.get_image() {
+            let rgba = RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into_owned());
+            return rgba.map(DynamicImage::from).ok_or_else(|| Box::new(ClipboardError::NoImage) as Box<dyn Error>);
+        }
+        match clipboard.get().file_list().ok().and_then(|files| files.into_iter().next()) {
+            Some(file) => read_image(&file),
+            None => Err(Box::new(ClipboardError::NoImage)),
+        }
+    }
+
+    pub fn copy_files(paths: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+        let paths = paths.iter().map(|path| path.canonicalize()).collect::<Result<Vec<PathBuf>, _>>()?;
+        Clipboard::new()?.set().file_list(&paths)?;
+        Ok(())
+    }
+
+    pub fn copy_data_uri(image: &DynamicImage, options: &ExportOptions) -> Result<(), Box<dyn Error>> {
+        Clipboard::new()?.set_text(data_uri(image, options)?)?;
+        Ok(())
+    }
+
+    pub fn copy_markdown(path: &Path) -> Result<(), Box<dyn Error>> {
+        let (markdown, html) = snippets(path)?;
+        Clipboard::new()?.set_html(html, Some(markdown))?;
+        Ok(())
+    }
+
+    /// PNG data URI of image, encoded with options but without metadata since it is meant to be shared
+    pub fn data_uri(image: &DynamicImage, options: &ExportOptions) -> Result<String, Box<dyn Error>> {
+        let mut png = Cursor::new(Vec::new());
+        encode_image(image, ImageFormat::Png, options, None, &mut png)?;
+        Ok(format!("data:image/png;base64,{}", base64(png.get_ref())))
+    }
+
+    /// file:// URI of an existing file
+    pub fn file_uri(path: &Path) -> Result<String, Box<dyn Error>> {
+        let path = path.canonicalize()?;
+        match Url::from_file_path(&path) {
+            Ok(url) => Ok(url.to_string()),
+            Err(_) => Err(Box::new(ClipboardError::InvalidPath(path.to_string_lossy().into_owned()))),
+        }
+    }
+
+    /// (Markdown, HTML) image snippets pointing to path, the file name is the alternative text
+    pub fn snippets(path: &Path) -> Result<(String, String), Box<dyn Error>> {
+        let uri = file_uri(path)?;
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        // parentheses would end the Markdown link early
+        let markdown = format!("![{}]({})", name.replace('[', "\\[").replace(']', "\\]"), uri.replace('(', "%28").replace(')', "%29"));
+        let html = format!("<img src=\"{}\" alt=\"{}\">", uri, name.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;"));
+        Ok((markdown, html))
+    }
+
+    /// Standard base64 with padding
+    pub fn base64(data: &[u8]) -> String {
+        let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+        for chunk in data.chunks(3) {
+            let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+            let group = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+            for index in 0..4 {
+                if index <= chunk.len() {
+                    encoded.push(BASE64_CHARS[(group >> (18 - 6 * index) & 0x3F) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+        encoded
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn base64_matches_rfc_4648() {
+            for (data, encoded) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy")] {
+                assert_eq!(base64(data.as_bytes()), encoded);
+            }
+            assert_eq!(base64(&[0xFB, 0xFF, 0xBF]), "+/+/");
+        }
+    }
+}
@@ -0,0 +1,100 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod export_module {
+    use std::error::Error;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::thread::{self, JoinHandle};
+    use std::time::Instant;
+
+    type Task = Box<dyn FnOnce() -> Result<String, Box<dyn Error>> + Send>;
+
+    struct ExportJob {
+        label: String,
+        task: Task,
+    }
+
+    /// Outcome of a finished job
+    pub struct ExportReport {
+        pub label: String,
+        /// message returned by the job, or its error
+        pub result: Result<String, String>,
+        pub finished: Instant,
+    }
+
+    /// Runs saves and clipboard copies one after the other on a worker thread, so that encoding large
+    /// images never blocks the UI. Dropping the worker waits for the jobs still queued
+    pub struct ExportWorker {
+        sender: Option<Sender<ExportJob>>,
+        receiver: Receiver<ExportReport>,
+        pending: Arc<AtomicUsize>,
+        handle: Option<JoinHandle<()>>,
+    }
+
+    impl ExportWorker {
+        pub fn new() -> ExportWorker {
+            let (sender, jobs) = channel::<ExportJob>();
+            let (reports, receiver) = channel();
+            let pending = Arc::new(AtomicUsize::new(0));
+            let pending_worker = pending.clone();
+            let handle = thread::spawn(move || {
+                // ends once the sender is dropped and the queue is empty
+                for job in jobs {
+                    let result = match catch_unwind(AssertUnwindSafe(job.task)) {
+                        Ok(result) => result.map_err(|e| e.to_string()),
+                        Err(_) => Err(String::from("the export stopped unexpectedly")),
+                    };
+                    pending_worker.fetch_sub(1, Ordering::SeqCst);
+                    // the receiver may be gone already, nothing to report in that case
+                    let _ = reports.send(ExportReport { label: job.label, result, finished: Instant::now() });
+                }
+            });
+            ExportWorker { sender: Some(sender), receiver, pending, handle: Some(handle) }
+        }
+
+        /// Queues task, label describes it in the report. The task returns the message shown when it succeeds
+        pub fn submit<T>(&self, label: &str, task: T)
+            where T: FnOnce() -> Result<String, Box<dyn Error>> + Send + 'static {
+            let job = ExportJob { label: label.to_string(), task: Box::new(task) };
+            self.pending.fetch_add(1, Ordering::SeqCst);
+            let sent = match &self.sender {
+                Some(sender) => sender.send(job).is_ok(),
+                None => false,
+            };
+            if !sent {
+                self.pending.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        /// Jobs queued or running
+        pub fn pending(&self) -> usize {
+            self.pending.load(Ordering::SeqCst)
+        }
+
+        /// Reports of the jobs finished since the last call
+        pub fn reports(&self) -> Vec<ExportReport> {
+            self.receiver.try_iter().collect()
+        }
+
+        /// Stops accepting jobs and waits until the queued ones are done
+        pub fn flush(&mut self) {
+            self.sender = None;
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    impl Default for ExportWorker {
+        fn default() -> ExportWorker {
+            ExportWorker::new()
+        }
+    }
+
+    impl Drop for ExportWorker {
+        fn drop(&mut self) {
+            self.flush();
+        }
+    }
+}
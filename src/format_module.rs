@@ -0,0 +1,369 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod format_module {
+    use std::borrow::Cow;
+    use std::error::Error;
+    use std::fs::File;
+    use std::io::{BufWriter, Seek, Write};
+    use std::path::Path;
+    use color_quant::NeuQuant;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{imageops, DynamicImage, ImageFormat};
+    use serde::{Deserialize, Serialize};
+    use thiserror::Error;
+    use crate::metadata_module::metadata_module::{CaptureMetadata, MetadataMode};
+
+    #[derive(Error, Debug)]
+    enum FormatError {
+        #[error("GIF images can not be larger than 65535x65535 pixels")]
+        GifTooLarge,
+    }
+
+    /// An image format screenshots can be saved in
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct OutputFormat {
+        /// label of the format in the save dialog and in the settings
+        pub name: &'static str,
+        /// extension given to the saved files, also the value stored in the settings
+        pub extension: &'static str,
+        /// other extensions accepted for the same format
+        pub aliases: &'static [&'static str],
+        pub format: ImageFormat,
+    }
+
+    /// Every output format, in the order they are offered to the user.
+    /// WebP is written with the lossless encoder
+    pub const OUTPUT_FORMATS: [OutputFormat; 7] = [
+        OutputFormat { name: "PNG", extension: "png", aliases: &[], format: ImageFormat::Png },
+        OutputFormat { name: "JPG", extension: "jpg", aliases: &["jpeg"], format: ImageFormat::Jpeg },
+        OutputFormat { name: "GIF", extension: "gif", aliases: &[], format: ImageFormat::Gif },
+        OutputFormat { name: "WebP", extension: "webp", aliases: &[], format: ImageFormat::WebP },
+        OutputFormat { name: "BMP", extension: "bmp", aliases: &[], format: ImageFormat::Bmp },
+        OutputFormat { name: "TIFF", extension: "tiff", aliases: &["tif"], format: ImageFormat::Tiff },
+        OutputFormat { name: "QOI", extension: "qoi", aliases: &[], format: ImageFormat::Qoi },
+    ];
+
+    /// Looks a format up by extension (case insensitive, with or without the leading dot)
+    pub fn from_extension(extension: &str) -> Option<OutputFormat> {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        OUTPUT_FORMATS.iter()
+            .find(|f| f.extension == extension || f.aliases.contains(&extension.as_str()))
+            .copied()
+    }
+
+    pub fn from_image_format(format: ImageFormat) -> Option<OutputFormat> {
+        OUTPUT_FORMATS.iter().find(|f| f.format == format).copied()
+    }
+
+    /// Extensions of every format that can be opened, output formats first
+    pub fn readable_extensions() -> Vec<&'static str> {
+        let mut extensions: Vec<&'static str> = OUTPUT_FORMATS.iter()
+            .flat_map(|output| std::iter::once(output.extension).chain(output.aliases.iter().copied()))
+            .collect();
+        // the AVIF decoder is not enabled
+        for format in ImageFormat::all().filter(|format| format.can_read() && *format != ImageFormat::Avif) {
+            for extension in format.extensions_str() {
+                if !extensions.contains(extension) {
+                    extensions.push(extension);
+                }
+            }
+        }
+        extensions
+    }
+
+    /// Decodes an image file, the format is guessed from the content so a wrong extension does not matter
+    pub fn read_image(path: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+        Ok(image::io::Reader::open(path)?.with_guessed_format()?.decode()?)
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum PngCompression {
+        Fast,
+        Default,
+        Best,
+    }
+
+    /// Filter applied to the rows before compression, Adaptive picks one per row
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum PngFilter {
+        NoFilter,
+        Sub,
+        Up,
+        Avg,
+        Paeth,
+        Adaptive,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum ScaleMode {
+        /// the image is exported at its size
+        Original,
+        /// scaled down to a percentage of the size
+        Percent,
+        /// scaled down to fit in width x height, smaller images are left as they are
+        MaxSize,
+        ExactSize,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum ScaleFilter {
+        /// keeps hard pixel edges, for pixel art and UI details
+        Nearest,
+        /// smooth, for photos and text
+        Lanczos3,
+    }
+
+    /// Size of the exported image, the screenshot being edited keeps its size
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct ScaleOptions {
+        pub mode: ScaleMode,
+        /// 1 to 100, exports are never enlarged by percentage
+        pub percent: u32,
+        /// bounds for MaxSize (0 for no limit) and size for ExactSize
+        pub width: u32,
+        pub height: u32,
+        /// ExactSize takes the height from the width and the image aspect ratio
+        pub keep_aspect: bool,
+        pub filter: ScaleFilter,
+    }
+
+    impl Default for ScaleOptions {
+        fn default() -> ScaleOptions {
+            ScaleOptions {
+                mode: ScaleMode::Original,
+                percent: 50,
+                width: 1920,
+                height: 1080,
+                keep_aspect: true,
+                filter: ScaleFilter::Lanczos3,
+            }
+        }
+    }
+
+    impl ScaleOptions {
+        /// Size an image of width x height is exported at, never below 1x1
+        pub fn target_size(&self, width: u32, height: u32) -> (u32, u32) {
+            let scaled = |factor: f64| (((width as f64 * factor).round() as u32).max(1), ((height as f64 * factor).round() as u32).max(1));
+            match self.mode {
+                ScaleMode::Original => (width, height),
+                ScaleMode::Percent => scaled(self.percent.clamp(1, 100) as f64 / 100.0),
+                ScaleMode::MaxSize => {
+                    let bound = |max: u32, size: u32| if max == 0 { 1.0 } else { max as f64 / size.max(1) as f64 };
+                    scaled(bound(self.width, width).min(bound(self.height, height)).min(1.0))
+                }
+                ScaleMode::ExactSize if self.keep_aspect => {
+                    let target_width = self.width.max(1);
+                    (target_width, ((height as f64 * target_width as f64 / width.max(1) as f64).round() as u32).max(1))
+                }
+                ScaleMode::ExactSize => (self.width.max(1), self.height.max(1)),
+            }
+        }
+    }
+
+    /// image resized according to options, borrowed when the size does not change
+    pub fn scale_image<'a>(image: &'a DynamicImage, options: &ScaleOptions) -> Cow<'a, DynamicImage> {
+        let (width, height) = options.target_size(image.width(), image.height());
+        if (width, height) == (image.width(), image.height()) {
+            return Cow::Borrowed(image);
+        }
+        let filter = match options.filter {
+            ScaleFilter::Nearest => imageops::FilterType::Nearest,
+            ScaleFilter::Lanczos3 => imageops::FilterType::Lanczos3,
+        };
+        Cow::Owned(image.resize_exact(width, height, filter))
+    }
+
+    /// Encoder settings used when saving, formats without options ignore them
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct ExportOptions {
+        /// 1 to 100
+        pub jpeg_quality: u8,
+        pub png_compression: PngCompression,
+        pub png_filter: PngFilter,
+        /// colors in the GIF palette, 2 to 256
+        pub gif_colors: u16,
+        /// Floyd-Steinberg dithering, hides the banding of gradients reduced to the palette
+        pub gif_dither: bool,
+        pub metadata: MetadataMode,
+        /// user note added to the metadata
+        pub note: String,
+        pub scale: ScaleOptions,
+    }
+
+    impl Default for ExportOptions {
+        fn default() -> ExportOptions {
+            ExportOptions {
+                jpeg_quality: 90,
+                png_compression: PngCompression::Default,
+                png_filter: PngFilter::Adaptive,
+                gif_colors: 256,
+                gif_dither: true,
+                metadata: MetadataMode::Embed,
+                note: String::new(),
+                scale: ScaleOptions::default(),
+            }
+        }
+    }
+
+    /// Writes image to path in format with the given encoder options. The metadata is embedded, written
+    /// to a sidecar file or left out according to options.metadata
+    pub fn write_image(image: &DynamicImage, path: &Path, format: ImageFormat, options: &ExportOptions, metadata: &CaptureMetadata) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let embedded = if options.metadata == MetadataMode::Embed { Some(metadata) } else { None };
+        encode_image(image, format, options, embedded, &mut writer)?;
+        writer.flush()?;
+        if options.metadata == MetadataMode::Sidecar {
+            metadata.write_sidecar(path)?;
+        }
+        Ok(())
+    }
+
+    /// Encodes image scaled as options.scale asks, metadata is only embedded in PNG and JPEG
+    pub fn encode_image<W: Write + Seek>(image: &DynamicImage, format: ImageFormat, options: &ExportOptions, metadata: Option<&CaptureMetadata>, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        let image = scale_image(image, &options.scale);
+        let image = image.as_ref();
+        match format {
+            ImageFormat::Jpeg => {
+                // JPEG has no alpha channel
+                let mut jpeg = Vec::new();
+                let mut encoder = JpegEncoder::new_with_quality(&mut jpeg, options.jpeg_quality.clamp(1, 100));
+                encoder.encode_image(&image.to_rgb8())?;
+                if let Some(metadata) = metadata {
+                    jpeg = metadata.insert_into_jpeg(jpeg);
+                }
+                writer.write_all(&jpeg)?;
+            }
+            ImageFormat::Png => encode_png(image, options, metadata, writer)?,
+            ImageFormat::Gif => encode_gif(image, options, writer)?,
+            _ => image.write_to(writer, format)?,
+        }
+        Ok(())
+    }
+
+    fn encode_png<W: Write>(image: &DynamicImage, options: &ExportOptions, metadata: Option<&CaptureMetadata>, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        let rgba = image.to_rgba8();
+        let mut encoder = png::Encoder::new(writer, rgba.width(), rgba.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(match options.png_compression {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Default,
+            PngCompression::Best => png::Compression::Best,
+        });
+        match options.png_filter {
+            PngFilter::Adaptive => encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive),
+            filter => {
+                encoder.set_adaptive_filter(png::AdaptiveFilterType::NonAdaptive);
+                encoder.set_filter(match filter {
+                    PngFilter::Sub => png::FilterType::Sub,
+                    PngFilter::Up => png::FilterType::Up,
+                    PngFilter::Avg => png::FilterType::Avg,
+                    PngFilter::Paeth => png::FilterType::Paeth,
+                    _ => png::FilterType::NoFilter,
+                });
+            }
+        }
+        if let Some(metadata) = metadata {
+            let (text, utf8_text) = metadata.png_text();
+            for (keyword, value) in text {
+                encoder.add_text_chunk(keyword, value)?;
+            }
+            for (keyword, value) in utf8_text {
+                encoder.add_itxt_chunk(keyword, value)?;
+            }
+            let pixels_per_meter = metadata.pixels_per_meter();
+            encoder.set_pixel_dims(Some(png::PixelDimensions { xppu: pixels_per_meter, yppu: pixels_per_meter, unit: png::Unit::Meter }));
+        }
+        let mut png_writer = encoder.write_header()?;
+        png_writer.write_image_data(rgba.as_raw())?;
+        png_writer.finish()?;
+        Ok(())
+    }
+
+    /// Reduces the image to a palette of options.gif_colors with NeuQuant, dithered if requested
+    fn encode_gif<W: Write>(image: &DynamicImage, options: &ExportOptions, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        if image.width() > u16::MAX as u32 || image.height() > u16::MAX as u32 {
+            return Err(Box::new(FormatError::GifTooLarge));
+        }
+        let mut rgba = image.to_rgba8();
+        let quantizer = NeuQuant::new(10, options.gif_colors.clamp(2, 256) as usize, rgba.as_raw());
+        if options.gif_dither {
+            imageops::dither(&mut rgba, &quantizer);
+        }
+        let indices = imageops::index_colors(&rgba, &quantizer);
+        let (width, height) = (rgba.width() as u16, rgba.height() as u16);
+        let palette = quantizer.color_map_rgb();
+        let mut encoder = gif::Encoder::new(writer, width, height, &palette)?;
+        let frame = gif::Frame::from_indexed_pixels(width, height, indices.into_raw(), None);
+        encoder.write_frame(&frame)?;
+        Ok(())
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn options(mode: ScaleMode, percent: u32, width: u32, height: u32, keep_aspect: bool) -> ScaleOptions {
+            ScaleOptions { mode, percent, width, height, keep_aspect, ..ScaleOptions::default() }
+        }
+
+        fn gradient() -> DynamicImage {
+            DynamicImage::from(image::RgbaImage::from_fn(64, 48, |x, y| image::Rgba([(x * 4) as u8, (y * 5) as u8, ((x + y) * 2) as u8, 255])))
+        }
+
+        fn encode(format: ImageFormat, options: &ExportOptions) -> Vec<u8> {
+            let mut bytes = std::io::Cursor::new(Vec::new());
+            encode_image(&gradient(), format, options, None, &mut bytes).unwrap();
+            bytes.into_inner()
+        }
+
+        #[test]
+        fn jpeg_quality_changes_the_size() {
+            let low = encode(ImageFormat::Jpeg, &ExportOptions { jpeg_quality: 10, ..ExportOptions::default() });
+            let high = encode(ImageFormat::Jpeg, &ExportOptions { jpeg_quality: 100, ..ExportOptions::default() });
+            assert!(low.len() < high.len());
+            assert_eq!(image::load_from_memory(&low).unwrap().width(), 64);
+        }
+
+        #[test]
+        fn png_options_stay_lossless() {
+            for png_compression in [PngCompression::Fast, PngCompression::Default, PngCompression::Best] {
+                for png_filter in [PngFilter::Adaptive, PngFilter::NoFilter, PngFilter::Sub, PngFilter::Up, PngFilter::Avg, PngFilter::Paeth] {
+                    let png = encode(ImageFormat::Png, &ExportOptions { png_compression, png_filter, ..ExportOptions::default() });
+                    assert_eq!(image::load_from_memory(&png).unwrap().to_rgba8(), gradient().to_rgba8());
+                }
+            }
+        }
+
+        #[test]
+        fn gif_palette_is_limited() {
+            for gif_dither in [false, true] {
+                let gif = encode(ImageFormat::Gif, &ExportOptions { gif_colors: 4, gif_dither, ..ExportOptions::default() });
+                let decoded = image::load_from_memory(&gif).unwrap().to_rgba8();
+                let colors: std::collections::HashSet<_> = decoded.pixels().collect();
+                assert!(colors.len() <= 4);
+            }
+        }
+
+        #[test]
+        fn target_size_by_mode() {
+            assert_eq!(options(ScaleMode::Original, 50, 10, 10, true).target_size(800, 600), (800, 600));
+            assert_eq!(options(ScaleMode::Percent, 50, 0, 0, true).target_size(801, 600), (401, 300));
+            assert_eq!(options(ScaleMode::MaxSize, 0, 400, 400, true).target_size(800, 600), (400, 300));
+            assert_eq!(options(ScaleMode::MaxSize, 0, 0, 150, true).target_size(800, 600), (200, 150));
+            assert_eq!(options(ScaleMode::ExactSize, 0, 200, 999, true).target_size(800, 600), (200, 150));
+            assert_eq!(options(ScaleMode::ExactSize, 0, 200, 50, false).target_size(800, 600), (200, 50));
+        }
+
+        #[test]
+        fn target_size_never_enlarges_by_percent_or_bound() {
+            assert_eq!(options(ScaleMode::Percent, 400, 0, 0, true).target_size(800, 600), (800, 600));
+            assert_eq!(options(ScaleMode::Percent, 0, 0, 0, true).target_size(800, 600), (8, 6));
+            assert_eq!(options(ScaleMode::MaxSize, 0, 4000, 4000, true).target_size(800, 600), (800, 600));
+            assert_eq!(options(ScaleMode::MaxSize, 0, 0, 0, true).target_size(800, 600), (800, 600));
+            assert_eq!(options(ScaleMode::Percent, 1, 0, 0, true).target_size(10, 1000), (1, 10));
+        }
+    }
+}
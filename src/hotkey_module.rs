@@ -4,18 +4,30 @@ pub mod hotkey_module {
     use global_hotkey::GlobalHotKeyManager;
     use global_hotkey::hotkey::{Code, HotKey, Modifiers};
 
+    /// Modifiers of the hotkeys that act outside the editor (cancel, record, repeat region): other
+    /// applications rarely bind CTRL + ALT + SHIFT, while CTRL + letter shortcuts are everywhere
+    pub const ACTION_MODIFIERS: Modifiers = Modifiers::CONTROL.union(Modifiers::ALT).union(Modifiers::SHIFT);
+
     pub enum KeyType {
         Quick,
         NewScreenshot,
         Save,
         Pen,
         Rubber,
+        Cancel,
+        Record,
+        RepeatRegion,
+        /// hotkey of the region preset with the given index
+        Preset(usize),
+        /// hotkey of the export profile with the given index
+        Profile(usize),
     }
 
     pub enum ActiveShortcuts {
         ScreenshotDone,
         ScreenshotWaiting,
         Pause,
+        Countdown,
     }
 
     pub struct HotkeyManager {
@@ -25,6 +37,11 @@ pub mod hotkey_module {
         save: (Option<HotKey>, bool),
         pen: (Option<HotKey>, bool),
         rubber: (Option<HotKey>, bool),
+        cancel: (Option<HotKey>, bool),
+        record: (Option<HotKey>, bool),
+        repeat_region: (Option<HotKey>, bool),
+        presets: Vec<(Option<HotKey>, bool)>,
+        profiles: Vec<(Option<HotKey>, bool)>,
     }
 
     impl HotkeyManager {
@@ -36,6 +53,11 @@ pub mod hotkey_module {
                 save: (None, true),
                 pen: (None, true),
                 rubber: (None, true),
+                cancel: (None, true),
+                record: (None, true),
+                repeat_region: (None, true),
+                presets: Vec::new(),
+                profiles: Vec::new(),
             })
         }
         pub fn register_new_hotkey(&mut self, modifier: Option<Modifiers>, key: Code, key_type: KeyType) -> Result<u32, Box<dyn Error>> {
@@ -110,7 +132,113 @@ pub mod hotkey_module {
                     }
                     Ok(hk.id())
                 }
+                KeyType::Cancel => {
+                    let bool_now = self.cancel.1;
+                    if let (Some(hotkey), true) = self.cancel {
+                        self.manager.unregister(hotkey)?;
+                    }
+                    let hk = HotKey::new(modifier, key);
+                    if bool_now {
+                        self.manager.register(hk)?;
+                        self.cancel = (Some(hk), true);
+                    } else {
+                        self.cancel = (Some(hk), false);
+                    }
+                    Ok(hk.id())
+                }
+                KeyType::Record => {
+                    let bool_now = self.record.1;
+                    if let (Some(hotkey), true) = self.record {
+                        self.manager.unregister(hotkey)?;
+                    }
+                    let hk = HotKey::new(modifier, key);
+                    if bool_now {
+                        self.manager.register(hk)?;
+                        self.record = (Some(hk), true);
+                    } else {
+                        self.record = (Some(hk), false);
+                    }
+                    Ok(hk.id())
+                }
+                KeyType::RepeatRegion => {
+                    let bool_now = self.repeat_region.1;
+                    if let (Some(hotkey), true) = self.repeat_region {
+                        self.manager.unregister(hotkey)?;
+                    }
+                    let hk = HotKey::new(modifier, key);
+                    if bool_now {
+                        self.manager.register(hk)?;
+                        self.repeat_region = (Some(hk), true);
+                    } else {
+                        self.repeat_region = (Some(hk), false);
+                    }
+                    Ok(hk.id())
+                }
+                KeyType::Preset(index) => {
+                    if self.presets.len() <= index {
+                        self.presets.resize(index + 1, (None, true));
+                    }
+                    let bool_now = self.presets[index].1;
+                    if let (Some(hotkey), true) = self.presets[index] {
+                        self.manager.unregister(hotkey)?;
+                    }
+                    let hk = HotKey::new(modifier, key);
+                    if bool_now {
+                        self.manager.register(hk)?;
+                        self.presets[index] = (Some(hk), true);
+                    } else {
+                        self.presets[index] = (Some(hk), false);
+                    }
+                    Ok(hk.id())
+                }
+                KeyType::Profile(index) => {
+                    if self.profiles.len() <= index {
+                        self.profiles.resize(index + 1, (None, true));
+                    }
+                    let bool_now = self.profiles[index].1;
+                    if let (Some(hotkey), true) = self.profiles[index] {
+                        self.manager.unregister(hotkey)?;
+                    }
+                    let hk = HotKey::new(modifier, key);
+                    if bool_now {
+                        self.manager.register(hk)?;
+                        self.profiles[index] = (Some(hk), true);
+                    } else {
+                        self.profiles[index] = (Some(hk), false);
+                    }
+                    Ok(hk.id())
+                }
+            }
+        }
+
+        /// Unregisters the hotkeys of every region preset, before binding the edited list again
+        pub fn clear_presets(&mut self) -> Result<(), Box<dyn Error>> {
+            for preset in self.presets.drain(..) {
+                if let (Some(hotkey), true) = preset {
+                    self.manager.unregister(hotkey)?;
+                }
+            }
+            Ok(())
+        }
+
+        /// Index of the region preset bound to the hotkey id
+        pub fn find_preset(&self, id: u32) -> Option<usize> {
+            self.presets.iter().position(|preset| preset.0.map(|hk| hk.id()) == Some(id))
+        }
+
+        /// Unregisters the hotkeys of every export profile, before binding the edited list again
+        pub fn clear_profiles(&mut self) -> Result<(), Box<dyn Error>> {
+            for profile in self.profiles.drain(..) {
+                if let (Some(hotkey), true) = profile {
+                    self.manager.unregister(hotkey)?;
+                }
             }
+            Ok(())
+        }
+
+        /// Index of the export profile bound to the hotkey id
+        pub fn find_profile(&self, id: u32) -> Option<usize> {
+            self.profiles.iter().position(|profile| profile.0.map(|hk| hk.id()) == Some(id))
         }
 
         pub fn disable_shortcut(&mut self, key_type: KeyType) -> Result<(), Box<dyn Error>> {
@@ -150,6 +278,45 @@ pub mod hotkey_module {
                     }
                     Ok(())
                 }
+                KeyType::Cancel => {
+                    if let (Some(hotkey), true) = self.cancel {
+                        self.manager.unregister(hotkey)?;
+                        self.cancel.1 = false;
+                    }
+                    Ok(())
+                }
+                KeyType::Record => {
+                    if let (Some(hotkey), true) = self.record {
+                        self.manager.unregister(hotkey)?;
+                        self.record.1 = false;
+                    }
+                    Ok(())
+                }
+                KeyType::RepeatRegion => {
+                    if let (Some(hotkey), true) = self.repeat_region {
+                        self.manager.unregister(hotkey)?;
+                        self.repeat_region.1 = false;
+                    }
+                    Ok(())
+                }
+                KeyType::Preset(index) => {
+                    if let Some(preset) = self.presets.get_mut(index) {
+                        if let (Some(hotkey), true) = *preset {
+                            self.manager.unregister(hotkey)?;
+                            preset.1 = false;
+                        }
+                    }
+                    Ok(())
+                }
+                KeyType::Profile(index) => {
+                    if let Some(profile) = self.profiles.get_mut(index) {
+                        if let (Some(hotkey), true) = *profile {
+                            self.manager.unregister(hotkey)?;
+                            profile.1 = false;
+                        }
+                    }
+                    Ok(())
+                }
             };
         }
         pub fn enable_shortcut(&mut self, key_type: KeyType) -> Result<(), Box<dyn Error>> {
@@ -189,6 +356,45 @@ pub mod hotkey_module {
                     }
                     Ok(())
                 }
+                KeyType::Cancel => {
+                    if let (Some(hotkey), false) = self.cancel {
+                        self.manager.register(hotkey)?;
+                        self.cancel.1 = true;
+                    }
+                    Ok(())
+                }
+                KeyType::Record => {
+                    if let (Some(hotkey), false) = self.record {
+                        self.manager.register(hotkey)?;
+                        self.record.1 = true;
+                    }
+                    Ok(())
+                }
+                KeyType::RepeatRegion => {
+                    if let (Some(hotkey), false) = self.repeat_region {
+                        self.manager.register(hotkey)?;
+                        self.repeat_region.1 = true;
+                    }
+                    Ok(())
+                }
+                KeyType::Preset(index) => {
+                    if let Some(preset) = self.presets.get_mut(index) {
+                        if let (Some(hotkey), false) = *preset {
+                            self.manager.register(hotkey)?;
+                            preset.1 = true;
+                        }
+                    }
+                    Ok(())
+                }
+                KeyType::Profile(index) => {
+                    if let Some(profile) = self.profiles.get_mut(index) {
+                        if let (Some(hotkey), false) = *profile {
+                            self.manager.register(hotkey)?;
+                            profile.1 = true;
+                        }
+                    }
+                    Ok(())
+                }
             };
         }
         pub fn set_active_shortcuts(&mut self, active_shortcuts: ActiveShortcuts) -> Result<(), Box<dyn Error>> {
@@ -199,6 +405,15 @@ pub mod hotkey_module {
                     self.disable_shortcut(KeyType::Rubber)?;
                     self.disable_shortcut(KeyType::NewScreenshot)?;
                     self.disable_shortcut(KeyType::Quick)?;
+                    self.disable_shortcut(KeyType::Cancel)?;
+                    self.disable_shortcut(KeyType::Record)?;
+                    self.disable_shortcut(KeyType::RepeatRegion)?;
+                    for index in 0..self.presets.len() {
+                        self.disable_shortcut(KeyType::Preset(index))?;
+                    }
+                    for index in 0..self.profiles.len() {
+                        self.disable_shortcut(KeyType::Profile(index))?;
+                    }
                 }
                 ActiveShortcuts::ScreenshotWaiting => {
                     self.disable_shortcut(KeyType::Rubber)?;
@@ -206,6 +421,15 @@ pub mod hotkey_module {
                     self.enable_shortcut(KeyType::Quick)?;
                     self.enable_shortcut(KeyType::NewScreenshot)?;
                     self.disable_shortcut(KeyType::Save)?;
+                    self.disable_shortcut(KeyType::Cancel)?;
+                    self.enable_shortcut(KeyType::Record)?;
+                    self.enable_shortcut(KeyType::RepeatRegion)?;
+                    for index in 0..self.presets.len() {
+                        self.enable_shortcut(KeyType::Preset(index))?;
+                    }
+                    for index in 0..self.profiles.len() {
+                        self.disable_shortcut(KeyType::Profile(index))?;
+                    }
                 }
                 ActiveShortcuts::ScreenshotDone => {
                     self.enable_shortcut(KeyType::Rubber)?;
@@ -213,6 +437,32 @@ pub mod hotkey_module {
                     self.enable_shortcut(KeyType::Quick)?;
                     self.enable_shortcut(KeyType::NewScreenshot)?;
                     self.enable_shortcut(KeyType::Save)?;
+                    self.disable_shortcut(KeyType::Cancel)?;
+                    self.enable_shortcut(KeyType::Record)?;
+                    self.enable_shortcut(KeyType::RepeatRegion)?;
+                    for index in 0..self.presets.len() {
+                        self.enable_shortcut(KeyType::Preset(index))?;
+                    }
+                    // profiles export the open screenshot
+                    for index in 0..self.profiles.len() {
+                        self.enable_shortcut(KeyType::Profile(index))?;
+                    }
+                }
+                ActiveShortcuts::Countdown => {
+                    self.disable_shortcut(KeyType::Save)?;
+                    self.disable_shortcut(KeyType::Pen)?;
+                    self.disable_shortcut(KeyType::Rubber)?;
+                    self.disable_shortcut(KeyType::NewScreenshot)?;
+                    self.disable_shortcut(KeyType::Quick)?;
+                    self.enable_shortcut(KeyType::Cancel)?;
+                    self.disable_shortcut(KeyType::Record)?;
+                    self.disable_shortcut(KeyType::RepeatRegion)?;
+                    for index in 0..self.presets.len() {
+                        self.disable_shortcut(KeyType::Preset(index))?;
+                    }
+                    for index in 0..self.profiles.len() {
+                        self.disable_shortcut(KeyType::Profile(index))?;
+                    }
                 }
             }
             return Ok(());
@@ -250,6 +500,21 @@ pub mod hotkey_module {
                     }
                     None
                 }
+                KeyType::Cancel => {
+                    self.cancel.0.map(|hotkey| hotkey.id())
+                }
+                KeyType::Record => {
+                    self.record.0.map(|hotkey| hotkey.id())
+                }
+                KeyType::RepeatRegion => {
+                    self.repeat_region.0.map(|hotkey| hotkey.id())
+                }
+                KeyType::Preset(index) => {
+                    self.presets.get(index).and_then(|preset| preset.0).map(|hotkey| hotkey.id())
+                }
+                KeyType::Profile(index) => {
+                    self.profiles.get(index).and_then(|profile| profile.0).map(|hotkey| hotkey.id())
+                }
             };
         }
     }
@@ -0,0 +1,123 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod interval_module {
+    use std::error::Error;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+    use chrono::Local;
+    use image::ImageFormat;
+    use serde::{Deserialize, Serialize};
+    use crate::capture_module::capture_module::{name_context, take_screenshot, CaptureSource};
+    use crate::format_module::format_module::ExportOptions;
+
+    /// When an interval capture ends
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum IntervalLimit {
+        /// after the given number of saved captures, skipped identical frames do not count
+        Count,
+        /// after the given number of minutes
+        Duration,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct IntervalOptions {
+        pub screen: usize,
+        pub every: Duration,
+        pub limit: IntervalLimit,
+        pub count: u32,
+        pub duration: Duration,
+        pub skip_identical: bool,
+        pub with_cursor: bool,
+        /// HiDPI captures are saved at their logical size
+        pub export_at_1x: bool,
+        pub format: ImageFormat,
+        pub export: ExportOptions,
+        pub folder: PathBuf,
+    }
+
+    /// Unattended periodic capture running on a worker thread, every frame is saved as
+    /// timelapse-<start time>-<sequence number>
+    pub struct IntervalCapture {
+        stop: Arc<AtomicBool>,
+        saved: Arc<AtomicU32>,
+        skipped: Arc<AtomicU32>,
+        handle: JoinHandle<Result<u32, String>>,
+    }
+
+    impl IntervalCapture {
+        pub fn start(source: Arc<dyn CaptureSource>, options: IntervalOptions) -> IntervalCapture {
+            let stop = Arc::new(AtomicBool::new(false));
+            let saved = Arc::new(AtomicU32::new(0));
+            let skipped = Arc::new(AtomicU32::new(0));
+            let (stop_worker, saved_worker, skipped_worker) = (stop.clone(), saved.clone(), skipped.clone());
+            let handle = thread::spawn(move || {
+                run(source.as_ref(), &options, &stop_worker, &saved_worker, &skipped_worker).map_err(|e| e.to_string())
+            });
+            IntervalCapture { stop, saved, skipped, handle }
+        }
+
+        pub fn stop(&self) {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+
+        pub fn saved(&self) -> u32 {
+            self.saved.load(Ordering::SeqCst)
+        }
+
+        pub fn skipped(&self) -> u32 {
+            self.skipped.load(Ordering::SeqCst)
+        }
+
+        pub fn is_finished(&self) -> bool {
+            self.handle.is_finished()
+        }
+
+        /// Waits for the worker and returns the number of saved frames
+        pub fn join(self) -> Result<u32, Box<dyn Error>> {
+            match self.handle.join() {
+                Ok(result) => result.map_err(|e| e.into()),
+                Err(_) => Err("interval capture worker panicked".into()),
+            }
+        }
+    }
+
+    fn run(source: &dyn CaptureSource, options: &IntervalOptions, stop: &AtomicBool, saved: &AtomicU32, skipped: &AtomicU32) -> Result<u32, Box<dyn Error>> {
+        let started = Instant::now();
+        // the start time is fixed in the template, {counter} numbers the frames and skips names already taken
+        let template = format!("timelapse-{}-{{counter:5}}", Local::now().format("%d-%m-%Y-%H-%M-%S"));
+        let mut previous: Option<Vec<u8>> = None;
+        loop {
+            let tick = Instant::now();
+            let done = match options.limit {
+                IntervalLimit::Count => saved.load(Ordering::SeqCst) >= options.count,
+                IntervalLimit::Duration => started.elapsed() >= options.duration,
+            };
+            if done || stop.load(Ordering::SeqCst) {
+                break;
+            }
+            let mut screenshot = take_screenshot(source, Duration::from_secs(0), options.screen, options.with_cursor)?;
+            if options.export_at_1x {
+                screenshot = screenshot.downscaled_to_1x();
+            }
+            let pixels = screenshot.get_image()?.into_bytes();
+            if options.skip_identical && previous.as_ref() == Some(&pixels) {
+                skipped.fetch_add(1, Ordering::SeqCst);
+            } else {
+                let context = name_context(source, options.screen, None);
+                screenshot.save_image(&options.folder, &template, &context, options.format, &options.export)?;
+                saved.fetch_add(1, Ordering::SeqCst);
+                previous = Some(pixels);
+            }
+            // a save slower than the interval reschedules from now instead of catching up in a burst
+            let now = Instant::now();
+            let next = if tick + options.every > now { tick + options.every } else { now + options.every };
+            // sleep until the next tick in short steps so that stop is noticed quickly
+            while Instant::now() < next && !stop.load(Ordering::SeqCst) {
+                thread::sleep(next.saturating_duration_since(Instant::now()).min(Duration::from_millis(100)));
+            }
+        }
+        Ok(saved.load(Ordering::SeqCst))
+    }
+}
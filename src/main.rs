@@ -2,11 +2,39 @@ mod screenshots_module;
 mod hotkey_module;
 mod settings_module;
 mod state_module;
+mod capture_module;
+mod selection_module;
+mod platform_module;
+mod recording_module;
+mod interval_module;
+mod stitch_module;
+mod format_module;
+mod naming_module;
+mod browser_module;
+mod pdf_module;
+mod metadata_module;
+mod clipboard_module;
+mod export_module;
 
+use std::path::Path;
+use std::sync::Arc;
 use eframe::{NativeOptions, egui, IconData};
+use crate::capture_module::capture_module::{CaptureSource, FileCapture, ScreensCapture};
 use crate::state_module::state_module::ScreenshotStr;
 
-fn build_gui() -> () {
+/// Chooses the capture backend: `--capture-dir <path>` serves the png files found in path as
+/// monitors, otherwise the real screens are used
+fn select_capture_source() -> Arc<dyn CaptureSource> {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--capture-dir") {
+        let path = args.get(position + 1).expect("Missing path after --capture-dir");
+        let source = FileCapture::new(Path::new(path)).expect("Failed to open capture dir");
+        return Arc::new(source);
+    }
+    Arc::new(ScreensCapture)
+}
+
+fn build_gui(capture_source: Arc<dyn CaptureSource>) -> () {
     let icon = image::open("./resources/icon.png").expect("Failed to open icon path").to_rgba8();
     let (icon_width, icon_height) = icon.dimensions();
 
@@ -47,7 +75,7 @@ fn build_gui() -> () {
         options,
         Box::new(|_cc| {
             _cc.egui_ctx.set_fonts(fonts);
-            Box::<ScreenshotStr>::new(ScreenshotStr::default())
+            Box::<ScreenshotStr>::new(ScreenshotStr::new(capture_source))
         }),
     ).unwrap();
     println!("closing eframe");
@@ -56,5 +84,5 @@ fn build_gui() -> () {
 
 fn main() {
     //HOTKEYS
-    build_gui();
+    build_gui(select_capture_source());
 }
@@ -0,0 +1,313 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod metadata_module {
+    use std::error::Error;
+    use std::path::{Path, PathBuf};
+    use chrono::{DateTime, Local};
+    use serde::{Deserialize, Serialize};
+
+    const APP_NAME: &str = "Rusty Capture";
+    /// Namespace of the XMP properties that have no standard equivalent
+    const XMP_NAMESPACE: &str = "urn:rusty-capture:xmp:1.0/";
+    /// Largest payload of a JPEG marker segment
+    const MAX_SEGMENT: usize = 0xFFFF - 2;
+
+    /// (keyword, text) pairs of PNG text chunks
+    pub type TextChunks = Vec<(String, String)>;
+
+    /// What happens to the capture details when a file is exported
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum MetadataMode {
+        /// written inside PNG (tEXt/iTXt) and JPEG (EXIF/XMP) files, the other formats get none
+        Embed,
+        /// written next to the image in a .json file
+        Sidecar,
+        /// nothing is written, for privacy
+        Strip,
+    }
+
+    /// When and where a capture was taken
+    #[derive(Debug, Clone)]
+    pub struct CaptureMetadata {
+        pub time: DateTime<Local>,
+        pub screen: Option<usize>,
+        /// physical resolution of the captured screen
+        pub screen_size: Option<(u32, u32)>,
+        pub width: u32,
+        pub height: u32,
+        pub scale_factor: f32,
+        /// free text from the user, empty for none
+        pub note: String,
+    }
+
+    pub fn app_version() -> String {
+        format!("{} {}", APP_NAME, env!("CARGO_PKG_VERSION"))
+    }
+
+    impl CaptureMetadata {
+        /// Keyword and text of the PNG text chunks, the note goes in an iTXt chunk since it may not be Latin-1
+        pub fn png_text(&self) -> (TextChunks, TextChunks) {
+            let mut text = vec![
+                (String::from("Software"), app_version()),
+                (String::from("Creation Time"), self.time.to_rfc2822()),
+                (String::from("Scale Factor"), self.scale_factor.to_string()),
+            ];
+            if let Some(screen) = self.screen {
+                text.push((String::from("Screen"), screen.to_string()));
+            }
+            if let Some((width, height)) = self.screen_size {
+                text.push((String::from("Screen Resolution"), format!("{}x{}", width, height)));
+            }
+            let mut utf8_text = Vec::new();
+            if !self.note.is_empty() {
+                utf8_text.push((String::from("Comment"), self.note.clone()));
+            }
+            (text, utf8_text)
+        }
+
+        /// Pixels per meter matching the scale factor, 96 dpi being 1x
+        pub fn pixels_per_meter(&self) -> u32 {
+            (self.scale_factor.max(0.01) * 96.0 / 0.0254).round() as u32
+        }
+
+        /// EXIF block (TIFF structure, little endian) with the date, the software, the image size and the note
+        fn exif(&self) -> Vec<u8> {
+            let date = self.time.format("%Y:%m:%d %H:%M:%S").to_string();
+            let offset = self.time.format("%:z").to_string();
+            let mut ifd0 = vec![
+                (0x0131, IfdValue::Ascii(app_version())),
+                (0x0132, IfdValue::Ascii(date.clone())),
+                (0x8769, IfdValue::Long(0)),
+            ];
+            if !self.note.is_empty() {
+                ifd0.insert(0, (0x010E, IfdValue::Ascii(self.note.clone())));
+            }
+            let exif_ifd = vec![
+                (0x9000, IfdValue::Undefined(b"0232".to_vec())),
+                (0x9003, IfdValue::Ascii(date)),
+                (0x9011, IfdValue::Ascii(offset)),
+                (0xA002, IfdValue::Long(self.width)),
+                (0xA003, IfdValue::Long(self.height)),
+            ];
+            // the Exif IFD follows IFD0 and its values
+            let exif_offset = 8 + ifd_size(&ifd0);
+            if let Some(pointer) = ifd0.iter_mut().find(|(tag, _)| *tag == 0x8769) {
+                pointer.1 = IfdValue::Long(exif_offset);
+            }
+            let mut tiff = b"II*\0".to_vec();
+            tiff.extend_from_slice(&8u32.to_le_bytes());
+            write_ifd(&mut tiff, &ifd0);
+            write_ifd(&mut tiff, &exif_ifd);
+            tiff
+        }
+
+        fn xmp(&self) -> String {
+            let mut properties = format!(
+                "xmp:CreateDate=\"{}\" xmp:CreatorTool=\"{}\" tiff:ImageWidth=\"{}\" tiff:ImageLength=\"{}\" rc:ScaleFactor=\"{}\"",
+                self.time.to_rfc3339(), xml_escape(&app_version()), self.width, self.height, self.scale_factor
+            );
+            if let Some(screen) = self.screen {
+                properties.push_str(&format!(" rc:Screen=\"{}\"", screen));
+            }
+            if let Some((width, height)) = self.screen_size {
+                properties.push_str(&format!(" rc:ScreenWidth=\"{}\" rc:ScreenHeight=\"{}\"", width, height));
+            }
+            let description = if self.note.is_empty() {
+                String::new()
+            } else {
+                format!("<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>", xml_escape(&self.note))
+            };
+            format!(
+                concat!(
+                    "<?xpacket begin=\"\u{FEFF}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>",
+                    "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+                    "<rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" ",
+                    "xmlns:tiff=\"http://ns.adobe.com/tiff/1.0/\" xmlns:rc=\"{}\" {}>{}</rdf:Description>",
+                    "</rdf:RDF></x:xmpmeta><?xpacket end=\"w\"?>"
+                ),
+                XMP_NAMESPACE, properties, description
+            )
+        }
+
+        /// Adds the EXIF and XMP APP1 segments to an encoded JPEG, after the JFIF header.
+        /// A segment too large for JPEG (a very long note) is left out
+        pub fn insert_into_jpeg(&self, jpeg: Vec<u8>) -> Vec<u8> {
+            let mut segments = Vec::new();
+            for payload in [[b"Exif\0\0".as_slice(), &self.exif()].concat(), [b"http://ns.adobe.com/xap/1.0/\0".as_slice(), self.xmp().as_bytes()].concat()] {
+                if payload.len() > MAX_SEGMENT {
+                    continue;
+                }
+                segments.extend_from_slice(&[0xFF, 0xE1]);
+                segments.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+                segments.extend_from_slice(&payload);
+            }
+            // SOI, then the APP0 segment when there is one
+            let mut position = 2;
+            if jpeg.len() > 6 && jpeg[2..4] == [0xFF, 0xE0] {
+                position += 2 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+            }
+            let position = position.min(jpeg.len());
+            [&jpeg[..position], &segments, &jpeg[position..]].concat()
+        }
+
+        pub fn to_json(&self) -> serde_json::Value {
+            serde_json::json!({
+                "software": app_version(),
+                "time": self.time.to_rfc3339(),
+                "screen": self.screen,
+                "screen_resolution": self.screen_size.map(|(width, height)| [width, height]),
+                "width": self.width,
+                "height": self.height,
+                "scale_factor": self.scale_factor,
+                "note": self.note,
+            })
+        }
+
+        /// Writes the metadata of the image at path to path.json (e.g. "shot.png.json")
+        pub fn write_sidecar(&self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+            let mut sidecar = path.as_os_str().to_owned();
+            sidecar.push(".json");
+            let sidecar = PathBuf::from(sidecar);
+            std::fs::write(&sidecar, serde_json::to_string_pretty(&self.to_json())?)?;
+            Ok(sidecar)
+        }
+    }
+
+    enum IfdValue {
+        /// written NUL terminated, characters outside ASCII become '?'
+        Ascii(String),
+        Long(u32),
+        Undefined(Vec<u8>),
+    }
+
+    impl IfdValue {
+        /// (type, count, bytes)
+        fn encode(&self) -> (u16, u32, Vec<u8>) {
+            match self {
+                IfdValue::Ascii(text) => {
+                    let mut bytes: Vec<u8> = text.chars().map(|c| if c.is_ascii() { c as u8 } else { b'?' }).collect();
+                    bytes.push(0);
+                    (2, bytes.len() as u32, bytes)
+                }
+                IfdValue::Long(value) => (4, 1, value.to_le_bytes().to_vec()),
+                IfdValue::Undefined(bytes) => (7, bytes.len() as u32, bytes.clone()),
+            }
+        }
+    }
+
+    /// Bytes taken by an IFD followed by the values that do not fit in its entries
+    fn ifd_size(entries: &[(u16, IfdValue)]) -> u32 {
+        let values: usize = entries.iter()
+            .map(|(_, value)| value.encode().2.len())
+            .filter(|len| *len > 4)
+            .map(|len| len + len % 2)
+            .sum();
+        (2 + 12 * entries.len() + 4 + values) as u32
+    }
+
+    /// Appends an IFD (entries sorted by tag, no next IFD) and its out of line values to tiff
+    fn write_ifd(tiff: &mut Vec<u8>, entries: &[(u16, IfdValue)]) {
+        let start = tiff.len();
+        let mut data_offset = start + 2 + 12 * entries.len() + 4;
+        let mut data = Vec::new();
+        tiff.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for (tag, value) in entries {
+            let (kind, count, mut bytes) = value.encode();
+            tiff.extend_from_slice(&tag.to_le_bytes());
+            tiff.extend_from_slice(&kind.to_le_bytes());
+            tiff.extend_from_slice(&count.to_le_bytes());
+            if bytes.len() <= 4 {
+                bytes.resize(4, 0);
+                tiff.extend_from_slice(&bytes);
+            } else {
+                tiff.extend_from_slice(&(data_offset as u32).to_le_bytes());
+                if bytes.len() % 2 == 1 {
+                    bytes.push(0);
+                }
+                data_offset += bytes.len();
+                data.extend_from_slice(&bytes);
+            }
+        }
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(&data);
+    }
+
+    fn xml_escape(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use chrono::TimeZone;
+
+        fn u16_at(tiff: &[u8], offset: usize) -> u16 {
+            u16::from_le_bytes([tiff[offset], tiff[offset + 1]])
+        }
+
+        fn u32_at(tiff: &[u8], offset: usize) -> u32 {
+            u32::from_le_bytes(tiff[offset..offset + 4].try_into().unwrap())
+        }
+
+        /// (tag, type, count, value or offset) of the IFD at offset
+        fn entries(tiff: &[u8], offset: usize) -> Vec<(u16, u16, u32, u32)> {
+            let count = u16_at(tiff, offset) as usize;
+            (0..count).map(|index| {
+                let entry = offset + 2 + 12 * index;
+                (u16_at(tiff, entry), u16_at(tiff, entry + 2), u32_at(tiff, entry + 4), u32_at(tiff, entry + 8))
+            }).collect()
+        }
+
+        fn ascii(tiff: &[u8], offset: u32, count: u32) -> &str {
+            std::str::from_utf8(&tiff[offset as usize..(offset + count - 1) as usize]).unwrap()
+        }
+
+        fn metadata(note: &str) -> CaptureMetadata {
+            CaptureMetadata {
+                time: Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap(),
+                screen: Some(0),
+                screen_size: Some((1920, 1080)),
+                width: 640,
+                height: 480,
+                scale_factor: 1.0,
+                note: note.to_string(),
+            }
+        }
+
+        #[test]
+        fn exif_ifds_are_linked() {
+            let tiff = metadata("a note").exif();
+            assert_eq!(&tiff[..4], b"II*\0");
+            let ifd0 = entries(&tiff, u32_at(&tiff, 4) as usize);
+            assert_eq!(ifd0.iter().map(|entry| entry.0).collect::<Vec<_>>(), vec![0x010E, 0x0131, 0x0132, 0x8769]);
+            assert_eq!(ascii(&tiff, ifd0[0].3, ifd0[0].2), "a note");
+            assert_eq!(ascii(&tiff, ifd0[1].3, ifd0[1].2), app_version());
+            assert_eq!(ascii(&tiff, ifd0[2].3, ifd0[2].2), "2024:03:09 14:05:07");
+
+            let exif = entries(&tiff, ifd0[3].3 as usize);
+            assert_eq!(exif.iter().map(|entry| entry.0).collect::<Vec<_>>(), vec![0x9000, 0x9003, 0x9011, 0xA002, 0xA003]);
+            assert_eq!(exif[0].3.to_le_bytes(), *b"0232");
+            assert_eq!(ascii(&tiff, exif[1].3, exif[1].2), "2024:03:09 14:05:07");
+            assert_eq!((exif[3].1, exif[3].3), (4, 640));
+            assert_eq!((exif[4].1, exif[4].3), (4, 480));
+            // out of line values start at even offsets and stay inside the block
+            for (_, _, count, offset) in ifd0.iter().chain(&exif).filter(|entry| entry.1 == 2 && entry.2 > 4) {
+                assert_eq!(offset % 2, 0);
+                assert!((offset + count) as usize <= tiff.len());
+            }
+        }
+
+        #[test]
+        fn exif_without_note() {
+            let tiff = metadata("").exif();
+            let ifd0 = entries(&tiff, 8);
+            assert_eq!(ifd0.iter().map(|entry| entry.0).collect::<Vec<_>>(), vec![0x0131, 0x0132, 0x8769]);
+            assert_eq!(ifd0[2].3, 8 + ifd_size(&[
+                (0x0131, IfdValue::Ascii(app_version())),
+                (0x0132, IfdValue::Ascii("2024:03:09 14:05:07".to_string())),
+                (0x8769, IfdValue::Long(0)),
+            ]));
+            assert_eq!(entries(&tiff, ifd0[2].3 as usize).len(), 5);
+        }
+    }
+}
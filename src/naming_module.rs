@@ -0,0 +1,266 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod naming_module {
+    use std::error::Error;
+    use std::fs::{self, OpenOptions};
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+    use chrono::format::{Item, StrftimeItems};
+    use chrono::{DateTime, Local};
+    use thiserror::Error;
+
+    /// Same names the application used before templates existed
+    pub const DEFAULT_TEMPLATE: &str = "screenshot-{date:%d-%m-%Y-%H-%M-%S_%3f}";
+    const DEFAULT_DATE: &str = "%Y-%m-%d";
+    const DEFAULT_TIME: &str = "%H-%M-%S";
+    /// Give up looking for a free name after this many existing files
+    const MAX_COUNTER: u32 = 100_000;
+    /// Characters not allowed in file names on at least one of the supported systems
+    const ILLEGAL_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+    const RESERVED_NAMES: &[&str] = &["CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9"];
+
+    #[derive(Error, Debug)]
+    pub enum TemplateError {
+        #[error("unknown placeholder {{{0}}} in the file name template")]
+        UnknownPlaceholder(String),
+        #[error("missing }} in the file name template")]
+        UnclosedPlaceholder,
+        #[error("invalid date format \"{0}\" in the file name template")]
+        InvalidDateFormat(String),
+        #[error("invalid counter width \"{0}\" in the file name template")]
+        InvalidCounterWidth(String),
+        #[error("the file name template gives an empty file name")]
+        EmptyName,
+        #[error("no free file name found for {0}")]
+        NoFreeName(String),
+    }
+
+    /// Details of a capture, used to fill the placeholders of a template and the exported metadata
+    #[derive(Debug, Clone)]
+    pub struct NameContext {
+        pub time: DateTime<Local>,
+        /// captured screen, None when unknown or when the capture spans all screens
+        pub screen: Option<usize>,
+        /// physical resolution of the captured screen, None when unknown
+        pub screen_size: Option<(u32, u32)>,
+        pub width: u32,
+        pub height: u32,
+        /// name of the region preset the capture comes from
+        pub preset: Option<String>,
+    }
+
+    impl NameContext {
+        pub fn new(screen: Option<usize>, preset: Option<String>) -> NameContext {
+            NameContext { time: Local::now(), screen, screen_size: None, width: 0, height: 0, preset }
+        }
+    }
+
+    fn format_time(time: &DateTime<Local>, format: &str) -> Result<String, TemplateError> {
+        // chrono panics when an invalid format is displayed, it is checked first
+        if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+            return Err(TemplateError::InvalidDateFormat(format.to_string()));
+        }
+        Ok(time.format(format).to_string())
+    }
+
+    /// Replaces characters that can not appear in a file name with '_'
+    pub fn sanitize(value: &str) -> String {
+        value.chars()
+            .map(|c| if ILLEGAL_CHARS.contains(&c) || c.is_control() { '_' } else { c })
+            .collect()
+    }
+
+    /// Makes a path component valid: no "." or "..", no trailing dots and spaces, no reserved device names
+    fn sanitize_component(component: &str) -> String {
+        let trimmed = component.trim().trim_end_matches(['.', ' ']);
+        if trimmed.is_empty() {
+            return String::new();
+        }
+        let stem = trimmed.split('.').next().unwrap_or("").to_uppercase();
+        if RESERVED_NAMES.contains(&stem.as_str()) {
+            return format!("_{}", trimmed);
+        }
+        trimmed.to_string()
+    }
+
+    fn is_separator(c: char) -> bool {
+        c == '-' || c == '_' || c == ' ' || c == '.'
+    }
+
+    /// Expands the placeholders of template, '/' separates subfolders. A placeholder that expands to
+    /// nothing also takes away the separator before it, so "shot-{preset}-{time}" gives "shot-12-00-00"
+    /// outside of presets. Templates without {preset} get the preset name appended to the file name
+    pub fn render(template: &str, context: &NameContext, counter: u32) -> Result<PathBuf, TemplateError> {
+        let mut rendered = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            // '\' is accepted as subfolder separator too
+            if c == '/' || c == '\\' {
+                rendered.push('/');
+                continue;
+            }
+            if c != '{' {
+                rendered.push_str(&sanitize(&c.to_string()));
+                continue;
+            }
+            let mut placeholder = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) => placeholder.push(c),
+                    None => return Err(TemplateError::UnclosedPlaceholder),
+                }
+            }
+            let (name, argument) = match placeholder.split_once(':') {
+                Some((name, argument)) => (name, Some(argument)),
+                None => (placeholder.as_str(), None),
+            };
+            let value = match name {
+                "date" => format_time(&context.time, argument.unwrap_or(DEFAULT_DATE))?,
+                "time" => format_time(&context.time, argument.unwrap_or(DEFAULT_TIME))?,
+                "screen" => context.screen.map(|screen| screen.to_string()).unwrap_or_default(),
+                "width" => context.width.to_string(),
+                "height" => context.height.to_string(),
+                "preset" => context.preset.clone().unwrap_or_default(),
+                "counter" => {
+                    let width = match argument {
+                        Some(width) => width.parse::<usize>().map_err(|_| TemplateError::InvalidCounterWidth(width.to_string()))?,
+                        None => 0,
+                    };
+                    format!("{:0width$}", counter, width = width)
+                }
+                _ => return Err(TemplateError::UnknownPlaceholder(placeholder.clone())),
+            };
+            // values never create subfolders, only the '/' written in the template does
+            let value = sanitize(&value);
+            if value.is_empty() && rendered.ends_with(is_separator) {
+                rendered.pop();
+            }
+            rendered.push_str(&value);
+        }
+        if !template.contains("{preset") {
+            if let Some(preset) = context.preset.as_deref().map(sanitize).filter(|preset| !preset.trim().is_empty()) {
+                rendered.push('-');
+                rendered.push_str(&preset);
+            }
+        }
+
+        let components: Vec<String> = rendered.split('/')
+            .map(sanitize_component)
+            .filter(|component| !component.is_empty())
+            .collect();
+        if components.is_empty() {
+            return Err(TemplateError::EmptyName);
+        }
+        Ok(components.iter().collect())
+    }
+
+    /// Checks the template by expanding it with sample values
+    pub fn check_template(template: &str) -> Result<(), TemplateError> {
+        render(template, &NameContext::new(Some(0), Some("preset".to_string())), 1).map(|_| ())
+    }
+
+    /// First path in folder, named after template with the given extension, not taken by an existing file.
+    /// {counter} counts up from 1 until the name is free, templates without it get "-2", "-3", ... on collisions.
+    /// The subfolders of the template are created and so is the file, empty, so that a concurrent save
+    /// can not pick the same name
+    pub fn free_path(folder: &Path, template: &str, context: &NameContext, extension: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let has_counter = template.contains("{counter");
+        for counter in 1..=MAX_COUNTER {
+            let mut relative = render(template, context, counter)?;
+            if !has_counter && counter > 1 {
+                let name = relative.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                relative.set_file_name(format!("{}-{}", name, counter));
+            }
+            let mut path = folder.join(relative).into_os_string();
+            path.push(".");
+            path.push(extension);
+            let path = PathBuf::from(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(path),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(Box::new(e)),
+            }
+        }
+        Err(Box::new(TemplateError::NoFreeName(template.to_string())))
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use chrono::TimeZone;
+
+        fn context() -> NameContext {
+            NameContext {
+                time: Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap(),
+                screen: Some(1),
+                screen_size: Some((1920, 1080)),
+                width: 640,
+                height: 480,
+                preset: None,
+            }
+        }
+
+        #[test]
+        fn placeholders_are_expanded() {
+            let path = render("{date}/shot-{preset}-{time}_{width}x{height}-{counter:3}", &context(), 7).unwrap();
+            assert_eq!(path, PathBuf::from("2024-03-09").join("shot-14-05-07_640x480-007"));
+            assert!(matches!(render("{nope}", &context(), 1), Err(TemplateError::UnknownPlaceholder(_))));
+            assert!(matches!(render("shot-{date", &context(), 1), Err(TemplateError::UnclosedPlaceholder)));
+            assert!(matches!(render("{counter:x}", &context(), 1), Err(TemplateError::InvalidCounterWidth(_))));
+        }
+
+        #[test]
+        fn presets_always_reach_the_name() {
+            let mut context = context();
+            context.preset = Some("Top bar".to_string());
+            assert_eq!(render("{date}/shot", &context, 1).unwrap(), PathBuf::from("2024-03-09").join("shot-Top bar"));
+            assert_eq!(render("{preset}_{time}", &context, 1).unwrap(), PathBuf::from("Top bar_14-05-07"));
+            assert_eq!(render(DEFAULT_TEMPLATE, &context, 1).unwrap().to_string_lossy().rsplit_once('-').unwrap().1, "Top bar");
+            context.preset = None;
+            assert!(render(DEFAULT_TEMPLATE, &context, 1).unwrap().to_string_lossy().starts_with("screenshot-09-03-2024-14-05-07_"));
+        }
+
+        #[test]
+        fn illegal_characters_are_replaced() {
+            let mut context = context();
+            context.preset = Some("a/b:c*?".to_string());
+            assert_eq!(render("x<y>|{preset}", &context, 1).unwrap(), PathBuf::from("x_y__a_b_c__"));
+            assert_eq!(sanitize("tab\there"), "tab_here");
+        }
+
+        #[test]
+        fn paths_can_not_leave_the_folder() {
+            assert_eq!(render("../../{screen}/./shot", &context(), 1).unwrap(), PathBuf::from("1").join("shot"));
+            assert!(matches!(render("../..", &context(), 1), Err(TemplateError::EmptyName)));
+            let mut context = context();
+            context.preset = Some("..".to_string());
+            assert_eq!(render("{preset}/shot", &context, 1).unwrap(), PathBuf::from("shot"));
+        }
+
+        #[test]
+        fn reserved_names_are_prefixed() {
+            assert_eq!(render("con", &context(), 1).unwrap(), PathBuf::from("_con"));
+            assert_eq!(render("LPT1.tar/nul", &context(), 1).unwrap(), PathBuf::from("_LPT1.tar").join("_nul"));
+            assert_eq!(render("console", &context(), 1).unwrap(), PathBuf::from("console"));
+        }
+
+        #[test]
+        fn free_path_counts_on_collisions() {
+            let folder = std::env::temp_dir().join(format!("rusty_capture_naming_{}", std::process::id()));
+            let _ = fs::remove_dir_all(&folder);
+            let first = free_path(&folder, "sub/shot", &context(), "png").unwrap();
+            let second = free_path(&folder, "sub/shot", &context(), "png").unwrap();
+            assert_eq!(first, folder.join("sub").join("shot.png"));
+            assert_eq!(second, folder.join("sub").join("shot-2.png"));
+            assert!(first.is_file() && second.is_file());
+
+            let counted: Vec<PathBuf> = (0..2).map(|_| free_path(&folder, "n{counter:2}", &context(), "jpg").unwrap()).collect();
+            assert_eq!(counted, vec![folder.join("n01.jpg"), folder.join("n02.jpg")]);
+            fs::remove_dir_all(folder).unwrap();
+        }
+    }
+}
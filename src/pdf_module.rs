@@ -0,0 +1,261 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod pdf_module {
+    use std::error::Error;
+    use std::io::Write;
+    use std::path::Path;
+    use chrono::{DateTime, Local};
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use image::DynamicImage;
+    use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+    use serde::{Deserialize, Serialize};
+    use crate::metadata_module::metadata_module::MetadataMode;
+
+    /// Space left around the content, in points (1/72 inch)
+    const MARGIN: f32 = 36.0;
+    const HEADER_FONT_SIZE: f32 = 11.0;
+    /// Height taken by the header line, including the gap below it
+    const HEADER_HEIGHT: f32 = 24.0;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum PageSize {
+        A4,
+        Letter,
+        /// the page takes the size of the image at the chosen DPI
+        Image,
+    }
+
+    impl PageSize {
+        /// (width, height) in points, None for Image
+        fn points(&self) -> Option<(f32, f32)> {
+            match self {
+                PageSize::A4 => Some((595.0, 842.0)),
+                PageSize::Letter => Some((612.0, 792.0)),
+                PageSize::Image => None,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum PdfLayout {
+        /// the whole image on one page, scaled down when it does not fit
+        FitToPage,
+        /// the image keeps the page width and continues on as many pages as needed, for tall captures
+        SplitPages,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct PdfOptions {
+        /// image pixels per inch on paper, images are never enlarged beyond this size
+        pub dpi: u32,
+        pub page_size: PageSize,
+        pub landscape: bool,
+        pub layout: PdfLayout,
+        /// header text written at the top of every page, nothing when empty
+        pub title: String,
+        /// adds the capture date and time to the header
+        pub timestamp: bool,
+    }
+
+    impl Default for PdfOptions {
+        fn default() -> PdfOptions {
+            PdfOptions {
+                dpi: 96,
+                page_size: PageSize::A4,
+                landscape: false,
+                layout: PdfLayout::FitToPage,
+                title: String::new(),
+                timestamp: true,
+            }
+        }
+    }
+
+    fn deflate(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    /// The standard Helvetica font only has Latin-1 glyphs, other characters are shown as '?'
+    fn latin1(text: &str) -> Vec<u8> {
+        text.chars().map(|c| if (c as u32) < 256 { c as u8 } else { b'?' }).collect()
+    }
+
+    /// Header line for options at the given time, None when there is nothing to write. The time
+    /// is left out when metadata is stripped
+    fn header_text(options: &PdfOptions, time: &DateTime<Local>, metadata: MetadataMode) -> Option<String> {
+        let mut parts = Vec::new();
+        if !options.title.trim().is_empty() {
+            parts.push(options.title.trim().to_string());
+        }
+        if options.timestamp && metadata != MetadataMode::Strip {
+            parts.push(time.format("%Y-%m-%d %H:%M:%S").to_string());
+        }
+        if parts.is_empty() { None } else { Some(parts.join(" - ")) }
+    }
+
+    /// Builds a PDF embedding image losslessly (deflated RGB with an alpha mask when needed),
+    /// time is the one written in the header. Stripping metadata leaves out the document information
+    /// (creator and title) and the time
+    pub fn encode_pdf(image: &DynamicImage, options: &PdfOptions, time: &DateTime<Local>, metadata: MetadataMode) -> Result<Vec<u8>, Box<dyn Error>> {
+        let header = header_text(options, time, metadata);
+        let header_height = if header.is_some() { HEADER_HEIGHT } else { 0.0 };
+        let dpi = options.dpi.max(1) as f32;
+        let (image_width, image_height) = (image.width() as f32 * 72.0 / dpi, image.height() as f32 * 72.0 / dpi);
+        let (page_width, page_height) = match options.page_size.points() {
+            Some((width, height)) if options.landscape => (height, width),
+            Some(size) => size,
+            None => (image_width + 2.0 * MARGIN, image_height + 2.0 * MARGIN + header_height),
+        };
+        let content_width = page_width - 2.0 * MARGIN;
+        let content_height = page_height - 2.0 * MARGIN - header_height;
+        let content_top = MARGIN + content_height;
+
+        // size of the image on paper and number of pages
+        let scale = match options.layout {
+            PdfLayout::FitToPage => (content_width / image_width).min(content_height / image_height).min(1.0),
+            PdfLayout::SplitPages => (content_width / image_width).min(1.0),
+        };
+        let (width, height) = (image_width * scale, image_height * scale);
+        let page_count = match options.layout {
+            PdfLayout::FitToPage => 1,
+            PdfLayout::SplitPages => ((height / content_height).ceil() as usize).max(1),
+        };
+
+        let catalog_id = Ref::new(1);
+        let tree_id = Ref::new(2);
+        let info_id = Ref::new(3);
+        let font_id = Ref::new(4);
+        let image_id = Ref::new(5);
+        let mask_id = Ref::new(6);
+        // each page is followed by its content stream
+        let page_ids: Vec<Ref> = (0..page_count).map(|page| Ref::new(7 + 2 * page as i32)).collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(tree_id);
+        pdf.pages(tree_id).kids(page_ids.iter().copied()).count(page_count as i32);
+        if metadata != MetadataMode::Strip {
+            let mut info = pdf.document_info(info_id);
+            info.creator(TextStr("Rusty Capture"));
+            if !options.title.trim().is_empty() {
+                info.title(TextStr(options.title.trim()));
+            }
+            info.finish();
+        }
+        pdf.type1_font(font_id).base_font(Name(b"Helvetica")).encoding_predefined(Name(b"WinAnsiEncoding"));
+
+        let rgba = image.to_rgba8();
+        let has_alpha = rgba.pixels().any(|pixel| pixel[3] < 255);
+        let rgb: Vec<u8> = rgba.pixels().flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect();
+        let rgb = deflate(&rgb)?;
+        let mut xobject = pdf.image_xobject(image_id, &rgb);
+        xobject.filter(Filter::FlateDecode);
+        xobject.width(image.width() as i32);
+        xobject.height(image.height() as i32);
+        xobject.color_space().device_rgb();
+        xobject.bits_per_component(8);
+        if has_alpha {
+            xobject.s_mask(mask_id);
+        }
+        xobject.finish();
+        if has_alpha {
+            let alpha: Vec<u8> = rgba.pixels().map(|pixel| pixel[3]).collect();
+            let alpha = deflate(&alpha)?;
+            let mut mask = pdf.image_xobject(mask_id, &alpha);
+            mask.filter(Filter::FlateDecode);
+            mask.width(image.width() as i32);
+            mask.height(image.height() as i32);
+            mask.color_space().device_gray();
+            mask.bits_per_component(8);
+            mask.finish();
+        }
+
+        let x = MARGIN + (content_width - width) / 2.0;
+        for (index, page_id) in page_ids.iter().enumerate() {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut content = Content::new();
+            if let Some(header) = &header {
+                content.begin_text();
+                content.set_font(Name(b"F1"), HEADER_FONT_SIZE);
+                content.next_line(MARGIN, page_height - MARGIN - HEADER_FONT_SIZE);
+                content.show(Str(&latin1(header)));
+                content.end_text();
+            }
+            // every page draws the whole image shifted up by the pages before it, clipped to the content area
+            let y = content_top - height + index as f32 * content_height;
+            content.save_state();
+            content.rect(MARGIN, MARGIN, content_width, content_height);
+            content.clip_nonzero();
+            content.end_path();
+            content.transform([width, 0.0, 0.0, height, x, y]);
+            content.x_object(Name(b"Im1"));
+            content.restore_state();
+            let content = content.finish();
+
+            let mut page = pdf.page(*page_id);
+            page.media_box(Rect::new(0.0, 0.0, page_width, page_height));
+            page.parent(tree_id);
+            page.contents(content_id);
+            let mut resources = page.resources();
+            resources.x_objects().pair(Name(b"Im1"), image_id);
+            resources.fonts().pair(Name(b"F1"), font_id);
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content);
+        }
+        Ok(pdf.finish())
+    }
+
+    pub fn write_pdf(image: &DynamicImage, path: &Path, options: &PdfOptions, time: &DateTime<Local>, metadata: MetadataMode) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, encode_pdf(image, options, time, metadata)?)?;
+        Ok(())
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use chrono::TimeZone;
+        use image::RgbImage;
+
+        fn count(haystack: &[u8], needle: &[u8]) -> usize {
+            haystack.windows(needle.len()).filter(|window| *window == needle).count()
+        }
+
+        fn encode(height: u32, layout: PdfLayout, metadata: MetadataMode) -> Vec<u8> {
+            let image = DynamicImage::from(RgbImage::new(400, height));
+            let options = PdfOptions { layout, timestamp: false, ..PdfOptions::default() };
+            encode_pdf(&image, &options, &Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap(), metadata).unwrap()
+        }
+
+        #[test]
+        fn tall_images_are_split_across_pages() {
+            // 3000 px at 96 dpi are 2250 pt, A4 leaves 770 pt for the content
+            let pdf = encode(3000, PdfLayout::SplitPages, MetadataMode::Embed);
+            assert!(pdf.starts_with(b"%PDF-"));
+            assert_eq!(count(&pdf, b"/Type /Page\n"), 3);
+            assert_eq!(count(&pdf, b"/Count 3"), 1);
+
+            let pdf = encode(3000, PdfLayout::FitToPage, MetadataMode::Embed);
+            assert_eq!(count(&pdf, b"/Type /Page\n"), 1);
+            let pdf = encode(10, PdfLayout::SplitPages, MetadataMode::Embed);
+            assert_eq!(count(&pdf, b"/Type /Page\n"), 1);
+        }
+
+        #[test]
+        fn stripped_pdf_has_no_document_info() {
+            assert_eq!(count(&encode(100, PdfLayout::FitToPage, MetadataMode::Embed), b"/Creator"), 1);
+            assert_eq!(count(&encode(100, PdfLayout::FitToPage, MetadataMode::Strip), b"/Creator"), 0);
+        }
+
+        #[test]
+        fn header_leaves_out_the_time_when_stripped() {
+            let time = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap();
+            let options = PdfOptions { title: " Report ".to_string(), ..PdfOptions::default() };
+            assert_eq!(header_text(&options, &time, MetadataMode::Embed).as_deref(), Some("Report - 2024-03-09 14:05:07"));
+            assert_eq!(header_text(&options, &time, MetadataMode::Strip).as_deref(), Some("Report"));
+            assert_eq!(header_text(&PdfOptions::default(), &time, MetadataMode::Strip), None);
+        }
+    }
+}
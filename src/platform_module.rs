@@ -0,0 +1,185 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod platform_module {
+    use std::error::Error;
+    use image::RgbaImage;
+
+    /// Image of the mouse pointer as drawn by the system
+    pub struct CursorImage {
+        pub image: RgbaImage,
+        /// point of the image that sits exactly on the pointer position
+        pub hotspot: (i32, i32),
+        /// pointer position in desktop coordinates
+        pub position: (i32, i32),
+    }
+
+    /// A top level application window
+    #[derive(Debug, Clone)]
+    pub struct WindowInfo {
+        pub id: u32,
+        pub title: String,
+        /// process owning the window, when the window manager reports it
+        pub pid: Option<u32>,
+        /// position of the client area in desktop coordinates
+        pub x: i32,
+        pub y: i32,
+        pub width: u32,
+        pub height: u32,
+        /// decorations added by the window manager as (left, right, top, bottom)
+        pub frame_extents: (u32, u32, u32, u32),
+    }
+
+    impl WindowInfo {
+        /// (x, y, width, height) of the window in desktop coordinates, with or without its decorations
+        pub fn area(&self, decorations: bool) -> (i32, i32, u32, u32) {
+            if !decorations {
+                return (self.x, self.y, self.width, self.height);
+            }
+            let (left, right, top, bottom) = self.frame_extents;
+            (self.x - left as i32, self.y - top as i32, self.width + left + right, self.height + top + bottom)
+        }
+    }
+
+    /// Position of the mouse pointer in desktop coordinates
+    #[cfg(target_os = "linux")]
+    pub fn cursor_position() -> Result<(i32, i32), Box<dyn Error>> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::ConnectionExt;
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+        let pointer = conn.query_pointer(root)?.reply()?;
+        Ok((pointer.root_x as i32, pointer.root_y as i32))
+    }
+
+    /// Position of the mouse pointer in desktop coordinates
+    #[cfg(target_os = "windows")]
+    pub fn cursor_position() -> Result<(i32, i32), Box<dyn Error>> {
+        use winapi::shared::windef::POINT;
+        use winapi::um::winuser::GetCursorPos;
+        let mut point = POINT { x: 0, y: 0 };
+        if unsafe { GetCursorPos(&mut point) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok((point.x, point.y))
+    }
+
+    /// Position of the mouse pointer in desktop coordinates
+    #[cfg(target_os = "macos")]
+    pub fn cursor_position() -> Result<(i32, i32), Box<dyn Error>> {
+        use core_graphics::event::CGEvent;
+        use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+        let source = CGEventSource::new(CGEventSourceStateID::CombinedSessionState).map_err(|_| "cannot create event source")?;
+        let event = CGEvent::new(source).map_err(|_| "cannot read the pointer position")?;
+        let location = event.location();
+        Ok((location.x as i32, location.y as i32))
+    }
+
+    /// Current pointer image read through the XFixes extension
+    #[cfg(target_os = "linux")]
+    pub fn cursor_image() -> Result<CursorImage, Box<dyn Error>> {
+        use x11rb::protocol::xfixes::ConnectionExt;
+        let (conn, _) = x11rb::connect(None)?;
+        conn.xfixes_query_version(4, 0)?.reply()?;
+        let cursor = conn.xfixes_get_cursor_image()?.reply()?;
+        // pixels are premultiplied ARGB, image wants straight RGBA
+        let mut rgba = Vec::with_capacity(cursor.cursor_image.len() * 4);
+        for pixel in &cursor.cursor_image {
+            let a = (pixel >> 24) & 0xff;
+            let unmultiply = |c: u32| (c * 255).checked_div(a).unwrap_or(0).min(255) as u8;
+            rgba.push(unmultiply((pixel >> 16) & 0xff));
+            rgba.push(unmultiply((pixel >> 8) & 0xff));
+            rgba.push(unmultiply(pixel & 0xff));
+            rgba.push(a as u8);
+        }
+        let image = match RgbaImage::from_raw(cursor.width as u32, cursor.height as u32, rgba) {
+            Some(image) => image,
+            None => return Err("invalid cursor image".into()),
+        };
+        Ok(CursorImage {
+            image,
+            hotspot: (cursor.xhot as i32, cursor.yhot as i32),
+            position: (cursor.x as i32, cursor.y as i32),
+        })
+    }
+
+    /// The pointer image is not read from the system on this platform
+    #[cfg(not(target_os = "linux"))]
+    pub fn cursor_image() -> Result<CursorImage, Box<dyn Error>> {
+        Err("cursor image not available on this platform".into())
+    }
+
+    /// Arrow pointer shipped with the application, used when the system one is unavailable
+    pub fn bundled_cursor_image(position: (i32, i32)) -> CursorImage {
+        let image = image::load_from_memory(include_bytes!("../resources/cursors/arrow.png"))
+            .expect("Failed to decode the bundled cursor")
+            .to_rgba8();
+        CursorImage { image, hotspot: (0, 0), position }
+    }
+
+    /// Visible top level windows from bottom to top, read from the EWMH client list of the window manager
+    #[cfg(target_os = "linux")]
+    pub fn list_windows() -> Result<Vec<WindowInfo>, Box<dyn Error>> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, Window};
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+        let atom = |name: &str| -> Result<u32, Box<dyn Error>> {
+            Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+        };
+        let property32 = |window: Window, property: u32, kind: AtomEnum| -> Result<Vec<u32>, Box<dyn Error>> {
+            let reply = conn.get_property(false, window, property, kind, 0, u32::MAX)?.reply()?;
+            Ok(reply.value32().map(|values| values.collect()).unwrap_or_default())
+        };
+
+        // the stacking list has the same windows ordered bottom to top, not every window manager provides it
+        let mut ids = property32(root, atom("_NET_CLIENT_LIST_STACKING")?, AtomEnum::WINDOW)?;
+        if ids.is_empty() {
+            ids = property32(root, atom("_NET_CLIENT_LIST")?, AtomEnum::WINDOW)?;
+        }
+        let net_wm_name = atom("_NET_WM_NAME")?;
+        let utf8_string = atom("UTF8_STRING")?;
+        let net_wm_pid = atom("_NET_WM_PID")?;
+        let net_wm_state = atom("_NET_WM_STATE")?;
+        let state_hidden = atom("_NET_WM_STATE_HIDDEN")?;
+        let frame_extents = atom("_NET_FRAME_EXTENTS")?;
+
+        let mut windows = Vec::new();
+        for id in ids {
+            // a window may be destroyed while it is inspected, it is skipped in that case
+            let info = (|| -> Result<Option<WindowInfo>, Box<dyn Error>> {
+                if property32(id, net_wm_state, AtomEnum::ATOM)?.contains(&state_hidden) {
+                    return Ok(None);
+                }
+                let mut title = conn.get_property(false, id, net_wm_name, utf8_string, 0, 1024)?.reply()?.value;
+                if title.is_empty() {
+                    title = conn.get_property(false, id, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 1024)?.reply()?.value;
+                }
+                let geometry = conn.get_geometry(id)?.reply()?;
+                let position = conn.translate_coordinates(id, root, 0, 0)?.reply()?;
+                let extents = property32(id, frame_extents, AtomEnum::CARDINAL)?;
+                Ok(Some(WindowInfo {
+                    id,
+                    title: String::from_utf8_lossy(&title).into_owned(),
+                    pid: property32(id, net_wm_pid, AtomEnum::CARDINAL)?.first().copied(),
+                    x: position.dst_x as i32,
+                    y: position.dst_y as i32,
+                    width: geometry.width as u32,
+                    height: geometry.height as u32,
+                    frame_extents: match extents.as_slice() {
+                        [left, right, top, bottom] => (*left, *right, *top, *bottom),
+                        _ => (0, 0, 0, 0),
+                    },
+                }))
+            })();
+            if let Ok(Some(window)) = info {
+                windows.push(window);
+            }
+        }
+        Ok(windows)
+    }
+
+    /// Windows are only enumerated on X11 for now
+    #[cfg(not(target_os = "linux"))]
+    pub fn list_windows() -> Result<Vec<WindowInfo>, Box<dyn Error>> {
+        Err("window listing not available on this platform".into())
+    }
+}
@@ -0,0 +1,296 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod recording_module {
+    use std::error::Error;
+    use std::fs::{self, File};
+    use std::io::{BufReader, BufWriter, Read, Write};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+    use chrono::Local;
+    use flate2::read::ZlibDecoder;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use image::codecs::gif::{GifEncoder, Repeat};
+    use image::{Delay, DynamicImage, Frame, RgbaImage};
+    use serde::{Deserialize, Serialize};
+    use thiserror::Error;
+    use crate::capture_module::capture_module::{capture_screen, draw_cursor, pixel_density, CaptureSource, ALL_SCREENS};
+
+    #[derive(Error, Debug)]
+    enum RecordingError {
+        #[error("no frame was recorded")]
+        NoFrames,
+        #[error("Path is not a dir")]
+        PathError,
+        #[error("a region can not be recorded on all screens")]
+        RegionOnAllScreens,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum AnimationFormat {
+        Gif,
+        Apng,
+    }
+
+    impl AnimationFormat {
+        pub fn extension(&self) -> &'static str {
+            match self {
+                AnimationFormat::Gif => "gif",
+                AnimationFormat::Apng => "png",
+            }
+        }
+    }
+
+    /// What to record and how
+    #[derive(Debug, Clone, Copy)]
+    pub struct RecordingOptions {
+        pub screen: usize,
+        /// (x, y, width, height) in logical units relative to the screen, the whole screen when None
+        pub area: Option<(i32, i32, u32, u32)>,
+        pub fps: u32,
+        pub max_duration: Duration,
+        pub format: AnimationFormat,
+        pub with_cursor: bool,
+    }
+
+    /// A recording running on a worker thread: frames are grabbed until stop is called or the
+    /// maximum duration is reached, each one is written to the output file as soon as the next
+    /// one is grabbed
+    pub struct Recorder {
+        stop: Arc<AtomicBool>,
+        handle: JoinHandle<Result<PathBuf, String>>,
+        started: Instant,
+        max_duration: Duration,
+    }
+
+    impl Recorder {
+        pub fn start(source: Arc<dyn CaptureSource>, options: RecordingOptions, folder: PathBuf) -> Result<Recorder, Box<dyn Error>> {
+            if options.area.is_some() && options.screen == ALL_SCREENS {
+                return Err(Box::new(RecordingError::RegionOnAllScreens));
+            }
+            if !folder.is_dir() {
+                return Err(Box::new(RecordingError::PathError));
+            }
+            let mut file_name = "recording-".to_string();
+            file_name.push_str(Local::now().format("%d-%m-%Y-%H-%M-%S_%3f").to_string().as_str());
+            let path = folder.join(file_name).with_extension(options.format.extension());
+            let mut writer = AnimationWriter::create(&path, options.format)?;
+            let stop = Arc::new(AtomicBool::new(false));
+            let stop_worker = stop.clone();
+            let handle = thread::spawn(move || {
+                let result = match record_frames(source.as_ref(), &options, &stop_worker, &mut writer) {
+                    Ok(0) => {
+                        writer.discard(&path);
+                        Err(Box::new(RecordingError::NoFrames) as Box<dyn Error>)
+                    }
+                    Ok(_) => writer.finish(&path),
+                    Err(e) => {
+                        writer.discard(&path);
+                        Err(e)
+                    }
+                };
+                result.map(|_| path).map_err(|e| e.to_string())
+            });
+            Ok(Recorder { stop, handle, started: Instant::now(), max_duration: options.max_duration })
+        }
+
+        /// Asks the worker to stop grabbing frames, the file is completed right after
+        pub fn stop(&self) {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+
+        pub fn is_stopping(&self) -> bool {
+            self.stop.load(Ordering::SeqCst) || self.started.elapsed() >= self.max_duration
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            self.started.elapsed().min(self.max_duration)
+        }
+
+        pub fn max_duration(&self) -> Duration {
+            self.max_duration
+        }
+
+        pub fn is_finished(&self) -> bool {
+            self.handle.is_finished()
+        }
+
+        /// Waits for the worker and returns the path of the saved animation
+        pub fn join(self) -> Result<PathBuf, Box<dyn Error>> {
+            match self.handle.join() {
+                Ok(result) => result.map_err(|e| e.into()),
+                Err(_) => Err("recording worker panicked".into()),
+            }
+        }
+    }
+
+    /// Grabs frames at the requested rate and writes each one once the time it stayed on screen is
+    /// known, so that only the last frame is kept in memory. Returns the number of frames written
+    fn record_frames(source: &dyn CaptureSource, options: &RecordingOptions, stop: &AtomicBool, writer: &mut AnimationWriter) -> Result<u32, Box<dyn Error>> {
+        let interval = Duration::from_secs(1) / options.fps.max(1);
+        let display = if options.screen == ALL_SCREENS { None } else { Some(source.display(options.screen)?) };
+        let started = Instant::now();
+        let mut pending: Option<(RgbaImage, Instant)> = None;
+        let mut frames = 0;
+        while !stop.load(Ordering::SeqCst) && started.elapsed() < options.max_duration {
+            let now = Instant::now();
+            let image = match (display, options.area) {
+                (Some(display), Some((x, y, width, height))) => {
+                    let mut image = source.capture_rect(&display, x, y, width, height)?;
+                    if options.with_cursor {
+                        if let Ok(cursor) = source.cursor_image() {
+                            let scale = pixel_density(&image, width);
+                            draw_cursor(&mut image, &cursor, (display.x + x, display.y + y), scale);
+                        }
+                    }
+                    image
+                }
+                _ => capture_screen(source, options.screen, options.with_cursor)?.image,
+            };
+            // the delay of the previous frame is the real time elapsed since it was grabbed
+            if let Some((previous, grabbed)) = pending.take() {
+                writer.write_frame(previous, now - grabbed)?;
+                frames += 1;
+            }
+            pending = Some((image.to_rgba8(), now));
+            let next = now + interval;
+            let now = Instant::now();
+            if next > now {
+                thread::sleep(next - now);
+            }
+        }
+        if let Some((last, _)) = pending {
+            writer.write_frame(last, interval)?;
+            frames += 1;
+        }
+        Ok(frames)
+    }
+
+    /// Output of a recording, frames are written one at a time
+    enum AnimationWriter {
+        /// gif delays are stored in hundredths of a second
+        Gif(GifEncoder<BufWriter<File>>),
+        /// an animated png needs the number of frames in its header: the frames are spooled,
+        /// compressed, to a file next to the output and encoded once the recording ends
+        Apng { spool: BufWriter<File>, spool_path: PathBuf, frames: u32 },
+    }
+
+    impl AnimationWriter {
+        fn create(path: &Path, format: AnimationFormat) -> Result<AnimationWriter, Box<dyn Error>> {
+            match format {
+                AnimationFormat::Gif => {
+                    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), 10);
+                    encoder.set_repeat(Repeat::Infinite)?;
+                    Ok(AnimationWriter::Gif(encoder))
+                }
+                AnimationFormat::Apng => {
+                    let spool_path = path.with_extension("frames");
+                    let spool = BufWriter::new(File::create(&spool_path)?);
+                    Ok(AnimationWriter::Apng { spool, spool_path, frames: 0 })
+                }
+            }
+        }
+
+        fn write_frame(&mut self, image: RgbaImage, delay: Duration) -> Result<(), Box<dyn Error>> {
+            match self {
+                AnimationWriter::Gif(encoder) => {
+                    let delay = Delay::from_numer_denom_ms(delay.as_millis() as u32, 1);
+                    encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+                }
+                AnimationWriter::Apng { spool, frames, .. } => {
+                    let mut compressed = ZlibEncoder::new(Vec::new(), Compression::fast());
+                    compressed.write_all(image.as_raw())?;
+                    let compressed = compressed.finish()?;
+                    let millis = delay.as_millis().min(u16::MAX as u128) as u16;
+                    spool.write_all(&millis.to_le_bytes())?;
+                    spool.write_all(&image.width().to_le_bytes())?;
+                    spool.write_all(&image.height().to_le_bytes())?;
+                    spool.write_all(&(compressed.len() as u64).to_le_bytes())?;
+                    spool.write_all(&compressed)?;
+                    *frames += 1;
+                }
+            }
+            Ok(())
+        }
+
+        /// Completes the file at path
+        fn finish(self, path: &Path) -> Result<(), Box<dyn Error>> {
+            match self {
+                // the gif trailer is written when the encoder is dropped
+                AnimationWriter::Gif(_) => Ok(()),
+                AnimationWriter::Apng { spool, spool_path, frames } => {
+                    let result = match spool.into_inner() {
+                        Ok(_) => encode_apng(&spool_path, frames, path),
+                        Err(e) => Err(Box::new(e.into_error()) as Box<dyn Error>),
+                    };
+                    let _ = fs::remove_file(&spool_path);
+                    result
+                }
+            }
+        }
+
+        /// Removes the incomplete files of a failed recording
+        fn discard(self, path: &Path) {
+            if let AnimationWriter::Apng { spool_path, .. } = &self {
+                let _ = fs::remove_file(spool_path);
+            }
+            drop(self);
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn read_spooled_frame(spool: &mut impl Read) -> Result<(RgbaImage, u16), Box<dyn Error>> {
+        let mut delay = [0u8; 2];
+        let mut size = [0u8; 4];
+        let mut length = [0u8; 8];
+        spool.read_exact(&mut delay)?;
+        spool.read_exact(&mut size)?;
+        let width = u32::from_le_bytes(size);
+        spool.read_exact(&mut size)?;
+        let height = u32::from_le_bytes(size);
+        spool.read_exact(&mut length)?;
+        let mut pixels = Vec::new();
+        ZlibDecoder::new(spool.take(u64::from_le_bytes(length))).read_to_end(&mut pixels)?;
+        match RgbaImage::from_raw(width, height, pixels) {
+            Some(image) => Ok((image, u16::from_le_bytes(delay))),
+            None => Err(Box::new(RecordingError::NoFrames)),
+        }
+    }
+
+    fn write_apng_frame<W: Write>(writer: &mut png::Writer<W>, image: RgbaImage, delay: u16, size: (u32, u32)) -> Result<(), Box<dyn Error>> {
+        writer.set_frame_delay(delay, 1000)?;
+        // frames of a different size (e.g. a monitor change) are stretched to the first one
+        if image.dimensions() == size {
+            writer.write_image_data(image.as_raw())?;
+        } else {
+            let resized = DynamicImage::from(image).resize_exact(size.0, size.1, image::imageops::FilterType::Triangle);
+            writer.write_image_data(resized.to_rgba8().as_raw())?;
+        }
+        Ok(())
+    }
+
+    /// Writes an endlessly looping animated png from the spooled frames, delays are stored in milliseconds
+    fn encode_apng(spool_path: &Path, frames: u32, path: &Path) -> Result<(), Box<dyn Error>> {
+        if frames == 0 {
+            return Err(Box::new(RecordingError::NoFrames));
+        }
+        let mut spool = BufReader::new(File::open(spool_path)?);
+        let (first, first_delay) = read_spooled_frame(&mut spool)?;
+        let (width, height) = first.dimensions();
+        let writer = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames, 0)?;
+        let mut writer = encoder.write_header()?;
+        write_apng_frame(&mut writer, first, first_delay, (width, height))?;
+        for _ in 1..frames {
+            let (image, delay) = read_spooled_frame(&mut spool)?;
+            write_apng_frame(&mut writer, image, delay, (width, height))?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
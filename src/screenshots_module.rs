@@ -1,28 +1,35 @@
 #![allow(dead_code)]
 pub mod screenshot_module {
-    use std::borrow::Cow;
     use std::error::Error;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use std::{cmp, thread};
     use std::time::Duration;
-    use arboard::{Clipboard, ImageData};
-    use chrono::Local;
-    use image::{DynamicImage, GenericImage, GenericImageView, ImageFormat, Rgba, RgbaImage};
+    use image::{imageops, DynamicImage, GenericImage, GenericImageView, ImageFormat, Rgba, RgbaImage};
     use imageproc::drawing::{draw_line_segment_mut, draw_hollow_rect_mut, draw_text_mut, draw_hollow_circle_mut, draw_polygon_mut};
     use imageproc::point::Point;
     use imageproc::rect::Rect;
-    use screenshots::Screen;
+    use crate::capture_module::capture_module::{pixel_density, Capture, CaptureSource, Monitor};
+    use crate::clipboard_module::clipboard_module::{copy_data_uri, copy_files, copy_image, copy_markdown, paste_image, ClipboardTarget};
+    use crate::format_module::format_module::{from_extension, from_image_format, read_image, scale_image, write_image, ExportOptions};
+    use crate::metadata_module::metadata_module::CaptureMetadata;
+    use crate::naming_module::naming_module::{free_path, NameContext};
+    use crate::pdf_module::pdf_module::{write_pdf, PdfOptions};
+    use chrono::Local;
     use thiserror::Error;
     use rusttype::{Scale, Font};
 
     #[derive(Error, Debug)]
-    enum ScreenShotError {
+    pub enum ScreenShotError {
         #[error("sizes are not compatible")]
         ResizeSize,
         #[error("Path is not a dir")]
         PathError,
         #[error("extension error")]
         ExtensionError,
+        #[error("the screenshot must be saved first")]
+        NotSaved,
+        #[error("area {width}x{height} at ({x},{y}) is outside of the {screen_width}x{screen_height} screen")]
+        AreaOutOfBounds { x: i32, y: i32, width: u32, height: u32, screen_width: u32, screen_height: u32 },
     }
 
     #[derive(Clone)]
@@ -30,6 +37,8 @@ pub mod screenshot_module {
         screenshot: DynamicImage,
         original_image: DynamicImage,
         intermediate_image: DynamicImage,
+        /// image pixels per logical unit of the screen it was captured on
+        scale_factor: f32,
     }
 
     impl Screenshot {
@@ -38,60 +47,170 @@ pub mod screenshot_module {
                 screenshot: DynamicImage::new_rgba8(0, 0),
                 original_image: DynamicImage::new_rgba8(0, 0),
                 intermediate_image: DynamicImage::new_rgba8(0, 0),
+                scale_factor: 1.0,
             }
         }
 
-        pub fn new(screen: Screen) -> Result<Screenshot, Box<dyn Error>> {
-            let image_captured = screen.capture()?;
-            let width = image_captured.width();
-            let height = image_captured.height();
-            let image_rgba = image_captured.rgba().to_owned();
-            let rgba_image = RgbaImage::from_raw(width, height, image_rgba).unwrap();
-            let image_obj = DynamicImage::from(rgba_image);
+        pub fn new(source: &dyn CaptureSource, display: &Monitor) -> Result<Screenshot, Box<dyn Error>> {
+            let image_captured = source.capture_display(display)?;
+            let scale_factor = pixel_density(&image_captured, display.width);
+            Ok(Screenshot::from_image(image_captured).with_scale_factor(scale_factor))
+        }
+
+        /// Captures only the given area of the screen, (x, y) being relative to its top left corner,
+        /// without grabbing the whole monitor first
+        pub fn capture_area(source: &dyn CaptureSource, display: &Monitor, x: i32, y: i32, width: u32, height: u32) -> Result<Screenshot, Box<dyn Error>> {
+            check_area(display, x, y, width, height)?;
+            let image_captured = source.capture_rect(display, x, y, width, height)?;
+            let scale_factor = pixel_density(&image_captured, width);
+            Ok(Screenshot::from_image(image_captured).with_scale_factor(scale_factor))
+        }
+
+        pub fn from_image(image: DynamicImage) -> Screenshot {
+            let image_obj = DynamicImage::from(image.to_rgba8());
             let original_obj = image_obj.clone();
             let intermediate_obj = image_obj.clone();
-            Ok(
-                Screenshot {
-                    screenshot: image_obj,
-                    original_image: original_obj,
-                    intermediate_image: intermediate_obj,
-                }
-            )
+            Screenshot {
+                screenshot: image_obj,
+                original_image: original_obj,
+                intermediate_image: intermediate_obj,
+                scale_factor: 1.0,
+            }
         }
 
-        pub fn save_image(&self, path: &PathBuf, format: ImageFormat) -> Result<(), Box<dyn Error>> {
-            if path.is_dir() == false {
-                return Err(Box::new(ScreenShotError::PathError));
+        /// Opens an image file of any format that can be decoded, to annotate it like a capture
+        pub fn open(path: &Path) -> Result<Screenshot, Box<dyn Error>> {
+            Ok(Screenshot::from_image(read_image(path)?))
+        }
+
+        /// Image on the clipboard, see paste_image
+        pub fn from_clipboard() -> Result<Screenshot, Box<dyn Error>> {
+            Ok(Screenshot::from_image(paste_image()?))
+        }
+
+        pub fn from_capture(capture: Capture) -> Screenshot {
+            Screenshot::from_image(capture.image).with_scale_factor(capture.scale_factor)
+        }
+
+        /// Image and scale factor of the screenshot, without the editing history
+        pub fn into_capture(self) -> Capture {
+            Capture { image: self.screenshot, scale_factor: self.scale_factor }
+        }
+
+        pub fn with_scale_factor(mut self, scale_factor: f32) -> Screenshot {
+            self.scale_factor = if scale_factor > 0.0 { scale_factor } else { 1.0 };
+            self
+        }
+
+        pub fn get_scale_factor(&self) -> f32 {
+            self.scale_factor
+        }
+
+        /// Copy holding only the edited image, enough to save or copy it without duplicating the editing history
+        pub fn to_export(&self) -> Screenshot {
+            Screenshot {
+                screenshot: self.screenshot.clone(),
+                original_image: DynamicImage::new_rgba8(0, 0),
+                intermediate_image: DynamicImage::new_rgba8(0, 0),
+                scale_factor: self.scale_factor,
             }
-            let mut file_name = "screenshot-".to_string();
-            file_name.push_str(Local::now().format("%d-%m-%Y-%H-%M-%S_%3f").to_string().as_str());
-            let mut path_with_file_name = path.join(PathBuf::from(file_name));
-            match format {
-                ImageFormat::Png => {
-                    path_with_file_name = path_with_file_name.with_extension(PathBuf::from("png"));
-                }
-                ImageFormat::Gif => {
-                    path_with_file_name = path_with_file_name.with_extension(PathBuf::from("gif"));
-                }
-                ImageFormat::Jpeg => {
-                    path_with_file_name = path_with_file_name.with_extension(PathBuf::from("jpg"));
-                }
-                _ => {
-                    return Err(Box::new(ScreenShotError::ExtensionError));
-                }
+        }
+
+        /// Copy resized to the logical size of the capture, unchanged when it is already at 1x
+        pub fn downscaled_to_1x(&self) -> Screenshot {
+            if self.scale_factor <= 1.0 {
+                return self.clone();
+            }
+            let width = ((self.screenshot.width() as f32 / self.scale_factor).round() as u32).max(1);
+            let height = ((self.screenshot.height() as f32 / self.scale_factor).round() as u32).max(1);
+            // the empty images of an export copy stay empty
+            let resize = |image: &DynamicImage| if image.width() == 0 { image.clone() } else { image.resize_exact(width, height, imageops::FilterType::Lanczos3) };
+            Screenshot {
+                screenshot: resize(&self.screenshot),
+                original_image: resize(&self.original_image),
+                intermediate_image: resize(&self.intermediate_image),
+                scale_factor: 1.0,
             }
-            self.screenshot.save_with_format(path_with_file_name, format)?;
-            return Ok(());
         }
 
-        pub fn save_to_clipboard(&self) -> Result<(), Box<dyn Error>> {
-            let mut clipboard = Clipboard::new()?;
-            clipboard.set_image(ImageData {
-                width: self.screenshot.width() as usize,
-                height: self.screenshot.height() as usize,
-                bytes: Cow::from(self.screenshot.as_bytes()),
-            })?;
-            Ok(())
+        /// Saves the image in the folder path under a name built from template, subfolders of the
+        /// template are created and an existing file is never overwritten
+        pub fn save_image(&self, path: &Path, template: &str, context: &NameContext, format: ImageFormat, options: &ExportOptions) -> Result<PathBuf, Box<dyn Error>> {
+            let output = match from_image_format(format) {
+                Some(output) => output,
+                None => return Err(Box::new(ScreenShotError::ExtensionError)),
+            };
+            let size = options.scale.target_size(self.screenshot.width(), self.screenshot.height());
+            let path_with_file_name = self.template_path(path, template, context, output.extension, size)?;
+            let result = write_image(&self.screenshot, &path_with_file_name, format, options, &self.metadata(context, options));
+            if result.is_err() {
+                // the empty file reserving the name is not left behind
+                let _ = std::fs::remove_file(&path_with_file_name);
+            }
+            result.map(|_| path_with_file_name)
+        }
+
+        /// Saves the image as a PDF document, named and placed like save_image does. The scaling and
+        /// the metadata mode of export apply, the other options are for image formats
+        pub fn save_pdf(&self, path: &Path, template: &str, context: &NameContext, options: &PdfOptions, export: &ExportOptions) -> Result<PathBuf, Box<dyn Error>> {
+            let size = export.scale.target_size(self.screenshot.width(), self.screenshot.height());
+            let path_with_file_name = self.template_path(path, template, context, "pdf", size)?;
+            let image = scale_image(&self.screenshot, &export.scale);
+            let result = write_pdf(&image, &path_with_file_name, options, &context.time, export.metadata);
+            if result.is_err() {
+                let _ = std::fs::remove_file(&path_with_file_name);
+            }
+            result.map(|_| path_with_file_name)
+        }
+
+        pub fn save_pdf_as(&self, path: &Path, options: &PdfOptions, export: &ExportOptions) -> Result<(), Box<dyn Error>> {
+            let image = scale_image(&self.screenshot, &export.scale);
+            write_pdf(&image, path, options, &Local::now(), export.metadata)
+        }
+
+        /// Capture details written to the exported files, sizes are the ones of the scaled image
+        fn metadata(&self, context: &NameContext, options: &ExportOptions) -> CaptureMetadata {
+            let (width, height) = options.scale.target_size(self.screenshot.width(), self.screenshot.height());
+            CaptureMetadata {
+                time: context.time,
+                screen: context.screen,
+                screen_size: context.screen_size,
+                width,
+                height,
+                scale_factor: self.scale_factor * width as f32 / self.screenshot.width().max(1) as f32,
+                note: options.note.clone(),
+            }
+        }
+
+        /// Free path in the folder path for a file named after template, the file and its subfolders are
+        /// created. size is the one of the exported image
+        fn template_path(&self, path: &Path, template: &str, context: &NameContext, extension: &str, size: (u32, u32)) -> Result<PathBuf, Box<dyn Error>> {
+            if !path.is_dir() {
+                return Err(Box::new(ScreenShotError::PathError));
+            }
+            let context = NameContext { width: size.0, height: size.1, ..context.clone() };
+            free_path(path, template, &context, extension)
+        }
+
+        /// Saves the image exactly at path, in the format given by its extension
+        pub fn save_image_as(&self, path: &Path, context: &NameContext, options: &ExportOptions) -> Result<(), Box<dyn Error>> {
+            let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+            let output = match from_extension(&extension) {
+                Some(output) => output,
+                None => return Err(Box::new(ScreenShotError::ExtensionError)),
+            };
+            write_image(&self.screenshot, path, output.format, options, &self.metadata(context, options))
+        }
+
+        /// Copies the image to the clipboard as target, file is the saved copy File and Markdown refer to
+        pub fn save_to_clipboard(&self, target: ClipboardTarget, file: Option<&Path>, options: &ExportOptions) -> Result<(), Box<dyn Error>> {
+            match (target, file) {
+                (ClipboardTarget::Image, _) => copy_image(&scale_image(&self.screenshot, &options.scale)),
+                (ClipboardTarget::DataUri, _) => copy_data_uri(&self.screenshot, options),
+                (ClipboardTarget::File, Some(file)) => copy_files(&[file.to_path_buf()]),
+                (ClipboardTarget::Markdown, Some(file)) => copy_markdown(file),
+                (_, None) => Err(Box::new(ScreenShotError::NotSaved)),
+            }
         }
 
         pub fn get_image(&self) -> Result<DynamicImage, Box<dyn Error>> {
@@ -126,9 +245,9 @@ pub mod screenshot_module {
             Ok(())
         }
 
-        pub fn screenshot_after_delay(duration: Duration, screen: Screen) -> Result<Screenshot, Box<dyn Error>> {
+        pub fn screenshot_after_delay(duration: Duration, source: &dyn CaptureSource, display: &Monitor) -> Result<Screenshot, Box<dyn Error>> {
             thread::sleep(duration);
-            Screenshot::new(screen)
+            Screenshot::new(source, display)
         }
 
         pub fn save_intermediate_image(&mut self) -> Result<(), Box<dyn Error>> {
@@ -371,4 +490,23 @@ pub mod screenshot_module {
             }
         }
     }
+
+    /// Fails with AreaOutOfBounds unless the non empty area (x, y, width, height) lies inside display,
+    /// coordinates being relative to its top left corner
+    pub fn check_area(display: &Monitor, x: i32, y: i32, width: u32, height: u32) -> Result<(), ScreenShotError> {
+        let inside = x >= 0 && y >= 0 && width > 0 && height > 0
+            && x as u64 + width as u64 <= display.width as u64
+            && y as u64 + height as u64 <= display.height as u64;
+        if !inside {
+            return Err(ScreenShotError::AreaOutOfBounds {
+                x,
+                y,
+                width,
+                height,
+                screen_width: display.width,
+                screen_height: display.height,
+            });
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,230 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod selection_module {
+    use eframe::egui::{CursorIcon, Pos2, Rect, Vec2};
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Handle {
+        TopLeft,
+        Top,
+        TopRight,
+        Right,
+        BottomRight,
+        Bottom,
+        BottomLeft,
+        Left,
+        Inside,
+    }
+
+    impl Handle {
+        pub fn cursor_icon(&self) -> CursorIcon {
+            match self {
+                Handle::TopLeft | Handle::BottomRight => CursorIcon::ResizeNwSe,
+                Handle::TopRight | Handle::BottomLeft => CursorIcon::ResizeNeSw,
+                Handle::Top | Handle::Bottom => CursorIcon::ResizeVertical,
+                Handle::Left | Handle::Right => CursorIcon::ResizeHorizontal,
+                Handle::Inside => CursorIcon::Move,
+            }
+        }
+    }
+
+    enum Drag {
+        /// a new rectangle is being drawn starting from the given point
+        New(Pos2),
+        /// an existing rectangle is being moved or resized
+        Edit(Handle, Pos2, Rect),
+    }
+
+    /// Rectangle selected by the user on top of a frozen capture, expressed in image pixels
+    pub struct RegionSelection {
+        bounds: Rect,
+        rect: Option<Rect>,
+        drag: Option<Drag>,
+    }
+
+    impl RegionSelection {
+        pub fn new(width: u32, height: u32) -> RegionSelection {
+            RegionSelection {
+                bounds: Rect::from_min_size(Pos2::ZERO, Vec2::new(width as f32, height as f32)),
+                rect: None,
+                drag: None,
+            }
+        }
+
+        pub fn rect(&self) -> Option<Rect> {
+            self.rect
+        }
+
+        pub fn is_dragging(&self) -> bool {
+            self.drag.is_some()
+        }
+
+        /// Positions of the eight resize handles, in the same order as the Handle variants
+        pub fn handles(&self) -> Vec<(Handle, Pos2)> {
+            match self.rect {
+                Some(r) => vec![
+                    (Handle::TopLeft, r.left_top()),
+                    (Handle::Top, r.center_top()),
+                    (Handle::TopRight, r.right_top()),
+                    (Handle::Right, r.right_center()),
+                    (Handle::BottomRight, r.right_bottom()),
+                    (Handle::Bottom, r.center_bottom()),
+                    (Handle::BottomLeft, r.left_bottom()),
+                    (Handle::Left, r.left_center()),
+                ],
+                None => Vec::new(),
+            }
+        }
+
+        /// Returns the handle under pos (handles win over the inside of the rectangle), tolerance is in image pixels
+        pub fn handle_at(&self, pos: Pos2, tolerance: f32) -> Option<Handle> {
+            for (handle, handle_pos) in self.handles() {
+                if (handle_pos - pos).length() <= tolerance {
+                    return Some(handle);
+                }
+            }
+            match self.rect {
+                Some(r) if r.contains(pos) => Some(Handle::Inside),
+                _ => None,
+            }
+        }
+
+        pub fn begin_drag(&mut self, pos: Pos2, tolerance: f32) {
+            let pos = self.bounds.clamp(pos);
+            self.drag = match (self.handle_at(pos, tolerance), self.rect) {
+                (Some(handle), Some(rect)) => Some(Drag::Edit(handle, pos, rect)),
+                _ => Some(Drag::New(pos)),
+            };
+        }
+
+        pub fn drag_to(&mut self, pos: Pos2) {
+            let pos = self.bounds.clamp(pos);
+            match self.drag {
+                Some(Drag::New(start)) => {
+                    self.rect = Some(Rect::from_two_pos(start, pos));
+                }
+                Some(Drag::Edit(Handle::Inside, start, rect)) => {
+                    self.rect = Some(self.keep_inside(rect.translate(pos - start)));
+                }
+                Some(Drag::Edit(handle, _, rect)) => {
+                    let (mut min, mut max) = (rect.min, rect.max);
+                    match handle {
+                        Handle::TopLeft => min = pos,
+                        Handle::Top => min.y = pos.y,
+                        Handle::TopRight => { min.y = pos.y; max.x = pos.x; }
+                        Handle::Right => max.x = pos.x,
+                        Handle::BottomRight => max = pos,
+                        Handle::Bottom => max.y = pos.y,
+                        Handle::BottomLeft => { max.y = pos.y; min.x = pos.x; }
+                        Handle::Left => min.x = pos.x,
+                        Handle::Inside => {}
+                    }
+                    // dragging a handle past the opposite side flips the rectangle
+                    self.rect = Some(Rect::from_two_pos(min, max));
+                }
+                None => {}
+            }
+        }
+
+        pub fn end_drag(&mut self) {
+            self.drag = None;
+            if let Some(r) = self.rect {
+                if r.width() < 1.0 || r.height() < 1.0 {
+                    self.rect = None;
+                }
+            }
+        }
+
+        /// Moves the selection by (dx, dy) pixels, or grows its bottom right corner when resize is true
+        pub fn nudge(&mut self, dx: f32, dy: f32, resize: bool) {
+            if let Some(r) = self.rect {
+                let delta = Vec2::new(dx, dy);
+                if resize {
+                    let max = self.bounds.clamp(r.max + delta);
+                    self.rect = Some(Rect::from_min_max(r.min, max.max(r.min + Vec2::splat(1.0))));
+                } else {
+                    self.rect = Some(self.keep_inside(r.translate(delta)));
+                }
+            }
+        }
+
+        /// Area to crop as (x, y, width, height) rounded to whole pixels
+        pub fn crop_area(&self) -> Option<(u32, u32, u32, u32)> {
+            let r = self.rect?.intersect(self.bounds);
+            let x = r.min.x.round() as u32;
+            let y = r.min.y.round() as u32;
+            let width = (r.max.x.round() as u32).saturating_sub(x);
+            let height = (r.max.y.round() as u32).saturating_sub(y);
+            if width == 0 || height == 0 {
+                return None;
+            }
+            Some((x, y, width, height))
+        }
+
+        fn keep_inside(&self, rect: Rect) -> Rect {
+            let mut offset = Vec2::ZERO;
+            if rect.min.x < self.bounds.min.x { offset.x = self.bounds.min.x - rect.min.x; }
+            if rect.max.x > self.bounds.max.x { offset.x = self.bounds.max.x - rect.max.x; }
+            if rect.min.y < self.bounds.min.y { offset.y = self.bounds.min.y - rect.min.y; }
+            if rect.max.y > self.bounds.max.y { offset.y = self.bounds.max.y - rect.max.y; }
+            rect.translate(offset)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn selection(min: (f32, f32), max: (f32, f32)) -> RegionSelection {
+            let mut selection = RegionSelection::new(100, 80);
+            selection.begin_drag(Pos2::new(min.0, min.1), 0.0);
+            selection.drag_to(Pos2::new(max.0, max.1));
+            selection.end_drag();
+            selection
+        }
+
+        #[test]
+        fn nudge_moves_and_stays_inside() {
+            let mut selection = selection((10.0, 10.0), (30.0, 20.0));
+            selection.nudge(5.0, -3.0, false);
+            assert_eq!(selection.crop_area(), Some((15, 7, 20, 10)));
+            selection.nudge(1000.0, 1000.0, false);
+            assert_eq!(selection.crop_area(), Some((80, 70, 20, 10)));
+        }
+
+        #[test]
+        fn nudge_resizes_from_the_bottom_right_corner() {
+            let mut selection = selection((10.0, 10.0), (30.0, 20.0));
+            selection.nudge(4.0, 2.0, true);
+            assert_eq!(selection.crop_area(), Some((10, 10, 24, 12)));
+            // never smaller than one pixel, never past the image
+            selection.nudge(-100.0, -100.0, true);
+            assert_eq!(selection.crop_area(), Some((10, 10, 1, 1)));
+            selection.nudge(500.0, 500.0, true);
+            assert_eq!(selection.crop_area(), Some((10, 10, 90, 70)));
+        }
+
+        #[test]
+        fn handle_drag_flips_the_rectangle() {
+            let mut selection = selection((10.0, 10.0), (30.0, 20.0));
+            selection.begin_drag(Pos2::new(30.0, 20.0), 2.0);
+            selection.drag_to(Pos2::new(5.0, 15.0));
+            selection.end_drag();
+            assert_eq!(selection.crop_area(), Some((5, 10, 5, 5)));
+        }
+
+        #[test]
+        fn crop_area_is_clipped_and_rounded() {
+            let selection = selection((10.4, 9.6), (200.0, 200.0));
+            assert_eq!(selection.crop_area(), Some((10, 10, 90, 70)));
+            let empty = selection_with_rect(Rect::from_min_max(Pos2::new(20.0, 20.0), Pos2::new(20.2, 40.0)));
+            assert_eq!(empty.crop_area(), None);
+        }
+
+        fn selection_with_rect(rect: Rect) -> RegionSelection {
+            let mut selection = RegionSelection::new(100, 80);
+            selection.rect = Some(rect);
+            selection
+        }
+    }
+}
+
@@ -5,6 +5,14 @@ pub mod settings_module {
     use serde_json;
     use global_hotkey::hotkey::Code;
     use std::str::FromStr;
+    use image::ImageFormat;
+    use crate::recording_module::recording_module::AnimationFormat;
+    use crate::interval_module::interval_module::IntervalLimit;
+    use crate::capture_module::capture_module::CaptureRegion;
+    use crate::clipboard_module::clipboard_module::ClipboardTarget;
+    use crate::format_module::format_module::{from_extension, ExportOptions};
+    use crate::naming_module::naming_module::{check_template, DEFAULT_TEMPLATE};
+    use crate::pdf_module::pdf_module::PdfOptions;
     use thiserror::Error;
 
     #[derive(Error, Debug)]
@@ -13,16 +21,141 @@ pub mod settings_module {
         PathError,
         #[error("Too Short!")]
         LengthError,
+        #[error("Unknown image format")]
+        UnknownFormat,
+        #[error("{0} is not a valid hotkey")]
+        InvalidHotkey(String),
+        #[error("hotkey {0} is used twice")]
+        DuplicateHotkey(String),
+        #[error("hotkey {0} is reserved by the desktop")]
+        ReservedHotkey(String),
+    }
+
+    const MAX_RECENT_FOLDERS: usize = 5;
+    /// CTRL + ALT + T opens a terminal and CTRL + ALT + L locks the screen on most Linux desktops
+    /// (CTRL + ALT + Delete can not be typed in the single letter hotkey field)
+    const RESERVED_PROFILE_KEYS: &[&str] = &["T", "L"];
+
+    /// Screen grabbed by the quick screenshot hotkey
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum QuickTarget {
+        /// the screen under the mouse pointer
+        Cursor,
+        /// the screen selected in the main window
+        Selected,
+    }
+
+    /// A named rectangle captured and saved directly, optionally bound to CTRL + SHIFT + hotkey
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct RegionPreset {
+        pub name: String,
+        pub region: CaptureRegion,
+        /// single letter, empty when the preset has no hotkey
+        pub hotkey: String,
+    }
+
+    impl RegionPreset {
+        pub fn get_hotkey(&self) -> Result<Option<Code>, Box<dyn Error>> {
+            if self.hotkey.is_empty() {
+                return Ok(None);
+            }
+            let code_str = format!("Key{}", self.hotkey.to_uppercase());
+            Ok(Some(Code::from_str(&code_str)?))
+        }
+    }
+
+    /// One file written by an export profile
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct ProfileOutput {
+        /// extension of the format, like Settings::format
+        pub format: String,
+        pub options: ExportOptions,
+        /// destination folder, the default folder when empty
+        pub folder: String,
+        /// file name template, the default template when empty
+        pub template: String,
+    }
+
+    impl Default for ProfileOutput {
+        fn default() -> ProfileOutput {
+            ProfileOutput {
+                format: String::from("png"),
+                options: ExportOptions::default(),
+                folder: String::new(),
+                template: String::new(),
+            }
+        }
+    }
+
+    /// A named set of outputs written in one go (e.g. a PNG to archive and a JPG to share),
+    /// optionally bound to CTRL + ALT + hotkey
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct ExportProfile {
+        pub name: String,
+        pub outputs: Vec<ProfileOutput>,
+        /// single letter, empty when the profile has no hotkey
+        pub hotkey: String,
+    }
+
+    impl Default for ExportProfile {
+        fn default() -> ExportProfile {
+            ExportProfile {
+                name: String::from("Profile"),
+                outputs: vec![ProfileOutput::default(), ProfileOutput { format: String::from("jpg"), ..ProfileOutput::default() }],
+                hotkey: String::new(),
+            }
+        }
+    }
+
+    impl ExportProfile {
+        pub fn get_hotkey(&self) -> Result<Option<Code>, Box<dyn Error>> {
+            if self.hotkey.is_empty() {
+                return Ok(None);
+            }
+            let code_str = format!("Key{}", self.hotkey.to_uppercase());
+            Ok(Some(Code::from_str(&code_str)?))
+        }
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(default)]
     pub struct Settings {
         pub quick: String,
         pub new_screenshot: String,
         pub save: String,
         pub pen: String,
         pub rubber: String,
+        pub cancel: String,
+        pub record: String,
+        pub repeat_region: String,
         pub path: String,
+        /// name of the saved files, see naming_module for the placeholders
+        pub file_name_template: String,
+        /// folders chosen with Save As, most recent first
+        pub recent_folders: Vec<String>,
+        pub include_app_window: bool,
+        pub capture_cursor: bool,
+        pub export_at_1x: bool,
+        pub window_decorations: bool,
+        pub presets: Vec<RegionPreset>,
+        pub profiles: Vec<ExportProfile>,
+        pub quick_target: QuickTarget,
+        pub format: String,
+        pub export: ExportOptions,
+        /// what the clipboard button copies
+        pub clipboard_target: ClipboardTarget,
+        pub pdf: PdfOptions,
+        pub record_fps: u32,
+        pub record_seconds: u32,
+        pub record_format: AnimationFormat,
+        pub record_last_region: bool,
+        pub interval_seconds: u32,
+        pub interval_limit: IntervalLimit,
+        pub interval_count: u32,
+        pub interval_minutes: u32,
+        pub interval_skip_identical: bool,
     }
 
     impl Default for Settings {
@@ -33,7 +166,32 @@ pub mod settings_module {
                 save: String::from("S"),
                 pen: String::from("P"),
                 rubber: String::from("R"),
+                cancel: String::from("K"),
+                record: String::from("G"),
+                repeat_region: String::from("L"),
                 path: String::from("./"),
+                file_name_template: String::from(DEFAULT_TEMPLATE),
+                recent_folders: Vec::new(),
+                include_app_window: false,
+                capture_cursor: false,
+                export_at_1x: false,
+                window_decorations: true,
+                presets: Vec::new(),
+                profiles: Vec::new(),
+                quick_target: QuickTarget::Cursor,
+                format: String::from("png"),
+                export: ExportOptions::default(),
+                clipboard_target: ClipboardTarget::Image,
+                pdf: PdfOptions::default(),
+                record_fps: 10,
+                record_seconds: 10,
+                record_format: AnimationFormat::Gif,
+                record_last_region: false,
+                interval_seconds: 5,
+                interval_limit: IntervalLimit::Count,
+                interval_count: 10,
+                interval_minutes: 10,
+                interval_skip_identical: true,
             }
         }
     }
@@ -64,9 +222,39 @@ pub mod settings_module {
             Ok(Code::from_str(&code_str)?)
         }
 
+        pub fn get_cancel_hotkey(&self) -> Result<Code, Box<dyn Error>> {
+            let code_str = format!("Key{}", self.cancel.to_uppercase());
+            Ok(Code::from_str(&code_str)?)
+        }
+
+        pub fn get_record_hotkey(&self) -> Result<Code, Box<dyn Error>> {
+            let code_str = format!("Key{}", self.record.to_uppercase());
+            Ok(Code::from_str(&code_str)?)
+        }
+
+        pub fn get_repeat_region_hotkey(&self) -> Result<Code, Box<dyn Error>> {
+            let code_str = format!("Key{}", self.repeat_region.to_uppercase());
+            Ok(Code::from_str(&code_str)?)
+        }
+
+        pub fn get_format(&self) -> Result<ImageFormat, Box<dyn Error>> {
+            match from_extension(&self.format) {
+                Some(output) => Ok(output.format),
+                None => Err(Box::new(SettingsError::UnknownFormat)),
+            }
+        }
+
         pub fn get_path(&self) -> String {
             self.path.clone()
         }
+
+        /// Moves folder to the front of the recent folders, the oldest ones are forgotten
+        pub fn add_recent_folder(&mut self, folder: &std::path::Path) {
+            let folder = folder.to_string_lossy().into_owned();
+            self.recent_folders.retain(|recent| recent != &folder);
+            self.recent_folders.insert(0, folder);
+            self.recent_folders.truncate(MAX_RECENT_FOLDERS);
+        }
     }
 
     pub fn read_settings_from_file(filename: String) -> Result<Settings, Box<dyn Error>> {
@@ -83,31 +271,74 @@ pub mod settings_module {
         }
     }
 
+    /// Writes settings to filename once they are valid, an invalid setting is reported and the file is left as it was
     pub fn write_settings_to_file(filename: String, settings: &Settings) -> Result<(), Box<dyn Error>> {
+        check_settings(settings)?;
         let file = std::fs::File::create(filename)?;
         let writer = std::io::BufWriter::new(file);
+        serde_json::to_writer(writer, settings)?;
+        Ok(())
+    }
 
+    fn check_settings(settings: &Settings) -> Result<(), Box<dyn Error>> {
         //check if path is valid
         if !std::path::Path::new(&settings.path).exists() {
-            let sett = Settings::default();
-            serde_json::to_writer(writer, &sett)?;
             return Err(Box::new(SettingsError::PathError));
         }
 
+        //check if the file name template is valid
+        check_template(&settings.file_name_template)?;
+
+        //check the outputs of the export profiles
+        for output in settings.profiles.iter().flat_map(|profile| profile.outputs.iter()) {
+            if from_extension(&output.format).is_none() {
+                return Err(Box::new(SettingsError::UnknownFormat));
+            }
+            if !output.template.trim().is_empty() {
+                check_template(&output.template)?;
+            }
+        }
 
         //check if hotkey is at least 1 character long
-        if settings.quick.len() < 1 ||
-            settings.new_screenshot.len() < 1 ||
-            settings.save.len() < 1 ||
-            settings.pen.len() < 1 ||
-            settings.rubber.len() < 1
+        if settings.quick.is_empty() ||
+            settings.new_screenshot.is_empty() ||
+            settings.save.is_empty() ||
+            settings.pen.is_empty() ||
+            settings.rubber.is_empty() ||
+            settings.cancel.is_empty() ||
+            settings.record.is_empty() ||
+            settings.repeat_region.is_empty() ||
+            settings.presets.iter().any(|preset| preset.name.trim().is_empty()) ||
+            settings.profiles.iter().any(|profile| profile.name.trim().is_empty())
         {
-            let sett = Settings::default();
-            serde_json::to_writer(writer, &sett)?;
-            Err(Box::new(SettingsError::LengthError))
-        } else {
-            serde_json::to_writer(writer, settings)?;
-            Ok(())
+            return Err(Box::new(SettingsError::LengthError));
+        }
+
+        //check the hotkeys sharing the same modifiers: CTRL, CTRL + ALT + SHIFT, the presets (CTRL + SHIFT) and the profiles (CTRL + ALT)
+        check_hotkeys([&settings.quick, &settings.new_screenshot, &settings.save, &settings.pen, &settings.rubber].into_iter().map(String::as_str), &[])?;
+        check_hotkeys([&settings.cancel, &settings.record, &settings.repeat_region].into_iter().map(String::as_str), &[])?;
+        check_hotkeys(settings.presets.iter().map(|preset| preset.hotkey.as_str()), &[])?;
+        check_hotkeys(settings.profiles.iter().map(|profile| profile.hotkey.as_str()), RESERVED_PROFILE_KEYS)?;
+        Ok(())
+    }
+
+    /// Single letter hotkeys sharing the same modifiers must be keys, each used once and not reserved;
+    /// empty ones are not bound
+    fn check_hotkeys<'a>(hotkeys: impl Iterator<Item = &'a str>, reserved: &[&str]) -> Result<(), SettingsError> {
+        let mut used: Vec<String> = Vec::new();
+        for hotkey in hotkeys.filter(|hotkey| !hotkey.is_empty()) {
+            let key = hotkey.to_uppercase();
+            if Code::from_str(&format!("Key{}", key)).is_err() {
+                return Err(SettingsError::InvalidHotkey(hotkey.to_string()));
+            }
+            if reserved.contains(&key.as_str()) {
+                return Err(SettingsError::ReservedHotkey(key));
+            }
+            if used.contains(&key) {
+                return Err(SettingsError::DuplicateHotkey(key));
+            }
+            used.push(key);
         }
+        Ok(())
     }
-}
\ No newline at end of file
+}
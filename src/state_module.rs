@@ -4,18 +4,31 @@ pub mod state_module {
     use eframe::egui::Context;
     use std::time::{Duration, Instant};
     use eframe::egui::Margin;
-    use image::{EncodableLayout, ImageFormat};
+    use image::{DynamicImage, EncodableLayout, ImageFormat};
     use crate::screenshots_module::screenshot_module::Screenshot;
     use crate::settings_module::settings_module::*;
-    use screenshots::Screen;
+    use std::sync::Arc;
+    use crate::capture_module::capture_module::{capture_origin, crop_to_window, name_context, window_pixel_area, Capture, CaptureJob, CaptureRegion, CaptureSource, Monitor, ScreensCapture, ALL_SCREENS};
+    use crate::selection_module::selection_module::RegionSelection;
+    use crate::platform_module::platform_module::WindowInfo;
+    use crate::stitch_module::stitch_module::{load_frames, stitch, ScrollCapture, Stitched};
+    use crate::format_module::format_module::{from_extension, readable_extensions, ExportOptions, PngCompression, PngFilter, ScaleFilter, ScaleMode, ScaleOptions, OUTPUT_FORMATS};
+    use crate::metadata_module::metadata_module::MetadataMode;
+    use crate::browser_module::browser_module::{BrowserMode, DialogResult, FileBrowser, NativeDialog};
+    use crate::clipboard_module::clipboard_module::ClipboardTarget;
+    use crate::export_module::export_module::{ExportReport, ExportWorker};
+    use crate::pdf_module::pdf_module::{PageSize, PdfLayout, PdfOptions};
+    use crate::naming_module::naming_module::{render, NameContext};
+    use crate::recording_module::recording_module::{AnimationFormat, Recorder, RecordingOptions};
+    use crate::interval_module::interval_module::{IntervalCapture, IntervalLimit, IntervalOptions};
     use eframe::{egui::{CentralPanel, Layout, Align, TextEdit, Direction, Key, Window, ComboBox, TopBottomPanel, CursorIcon}, App, epaint::{ColorImage, Vec2, Pos2}, egui};
-    use std::{cmp, path::PathBuf};
-    use eframe::egui::{Color32, Frame, Rect, Slider};
+    use std::{cmp, path::{Path, PathBuf}};
+    use eframe::egui::{Align2, Area, Button, Color32, DragValue, FontId, Frame, Rect, RichText, ScrollArea, Sense, Slider};
     use eframe::epaint::Stroke;
     use global_hotkey::GlobalHotKeyEvent;
     use global_hotkey::hotkey::Modifiers;
     use rusttype::Scale;
-    use crate::hotkey_module::hotkey_module::{ActiveShortcuts, HotkeyManager, KeyType};
+    use crate::hotkey_module::hotkey_module::{ActiveShortcuts, HotkeyManager, KeyType, ACTION_MODIFIERS};
 
 
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -50,6 +63,40 @@ pub mod state_module {
         Arrow,
     }
 
+    /// Steps of a capture session, from hiding the window to restoring it
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum CaptureState {
+        Idle,
+        /// the window has to be hidden before capturing
+        Hiding,
+        /// the worker waits until the hidden window has been repainted, then captures
+        Capturing,
+        /// the frozen capture is shown full screen to select a region
+        Selecting,
+        /// the window is shown again with its previous geometry
+        Restoring,
+    }
+
+    /// What a capture session produces once the screen has been captured
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum CaptureMode {
+        /// a region dragged on the frozen capture
+        Region,
+        /// the window clicked on the frozen capture
+        PickWindow,
+        /// the window with the given id, chosen by title
+        Window(u32),
+    }
+
+    /// Frames painted after hiding the window before the capture starts, so that the compositor
+    /// has redrawn the desktop without it
+    const HIDE_FRAMES: u64 = 2;
+    /// Longest wait for those frames: a hidden window may not be repainted at all on some
+    /// platforms, the capture starts anyway afterwards
+    const HIDE_TIMEOUT: Duration = Duration::from_millis(500);
+    /// How long a finished export stays on screen, failures stay twice as long
+    const TOAST_DURATION: Duration = Duration::from_secs(4);
+
     pub struct ScreenshotStr {
         pub timer: usize,
         pub screen: usize,
@@ -70,22 +117,67 @@ pub mod state_module {
         pub tool_color: [u8; 3],
         pub tool_size: f32,
         pub settings_dialog: bool,
+        /// screens listed when the settings dialog was opened, for the file name preview
+        pub settings_screens: Vec<Monitor>,
         pub settings: Settings,
         pub instant: Instant,
         pub starting_point: Option<(f32, f32)>,
         pub upper_panel_size: Vec2,
-        pub screen_state: u8,
-        pub screenshot_taken: bool,
+        pub capture_state: CaptureState,
         pub image_converted: bool,
         pub window_pos: Pos2,
         pub window_size: Vec2,
+        pub window_maximized: bool,
         pub crop_screenshot_tmp: Screenshot,
-        pub saved_to_clipboard_dialog: bool,
         pub hotkey_manager: HotkeyManager,
+        pub capture_source: Arc<dyn CaptureSource>,
+        pub region_selection: Option<RegionSelection>,
+        pub frozen_capture: DynamicImage,
+        pub frozen_scale_factor: f32,
+        pub selection_texture: Option<egui::TextureHandle>,
+        pub countdown: Option<Instant>,
+        pub capture_job: Option<CaptureJob>,
+        /// frame in which the window was hidden, while the capture job waits for it to be repainted
+        pub hidden_frame: Option<u64>,
+        pub quick_job: Option<(CaptureJob, ImageFormat, PathBuf, usize)>,
+        /// capture of a region preset (saved with its name) or of the last region (opened in the editor)
+        pub region_job: Option<(CaptureJob, CaptureRegion, Option<String>)>,
+        pub last_region: Option<CaptureRegion>,
+        pub recorder: Option<Recorder>,
+        pub interval_capture: Option<IntervalCapture>,
+        pub capture_mode: CaptureMode,
+        pub window_dialog: bool,
+        /// in-app Save As browser, open when it is Some
+        pub file_browser: Option<FileBrowser>,
+        /// system file dialog open on a worker thread
+        pub native_dialog: Option<NativeDialog>,
+        /// Save As target that already exists, waiting for the user to confirm
+        pub overwrite_path: Option<PathBuf>,
+        /// saves and clipboard copies run here, the results are shown as toasts
+        pub export_worker: ExportWorker,
+        /// reports of the finished exports still on screen
+        pub toasts: Vec<ExportReport>,
+        pub window_list: Vec<WindowInfo>,
+        /// windows of other applications in the frozen capture, bottom to top
+        pub pick_windows: Vec<WindowInfo>,
+        /// desktop coordinates of the top left corner of the frozen capture
+        pub pick_origin: (i32, i32),
+        pub stitch_dialog: bool,
+        pub stitch_folder: String,
+        pub stitch_use_region: bool,
+        pub scroll_capture: Option<ScrollCapture>,
+        /// stitched image waiting to be accepted, with its scale factor and preview texture
+        pub stitch_preview: Option<(Stitched, f32, Option<egui::TextureHandle>)>,
     }
 
     impl Default for ScreenshotStr {
         fn default() -> Self {
+            ScreenshotStr::new(Arc::new(ScreensCapture))
+        }
+    }
+
+    impl ScreenshotStr {
+        pub fn new(capture_source: Arc<dyn CaptureSource>) -> Self {
             let mut tmp = HotkeyManager::new().unwrap();
             let startup_settings = read_settings_from_file("settings.json".to_string()).unwrap();
             let key_quick = startup_settings.get_quick_hotkey();
@@ -93,12 +185,26 @@ pub mod state_module {
             let key_pen = startup_settings.get_pen_hotkey();
             let key_rubber = startup_settings.get_rubber_hotkey();
             let key_save = startup_settings.get_save_hotkey();
+            let key_cancel = startup_settings.get_cancel_hotkey();
+            let key_record = startup_settings.get_record_hotkey();
+            let key_repeat_region = startup_settings.get_repeat_region_hotkey();
 
             tmp.register_new_hotkey(Some(Modifiers::CONTROL), key_screenshot.unwrap(), KeyType::NewScreenshot).unwrap();
             tmp.register_new_hotkey(Some(Modifiers::CONTROL), key_quick.unwrap(), KeyType::Quick).unwrap();
             tmp.register_new_hotkey(Some(Modifiers::CONTROL), key_pen.unwrap(), KeyType::Pen).unwrap();
             tmp.register_new_hotkey(Some(Modifiers::CONTROL), key_rubber.unwrap(), KeyType::Rubber).unwrap();
             tmp.register_new_hotkey(Some(Modifiers::CONTROL), key_save.unwrap(), KeyType::Save).unwrap();
+            tmp.register_new_hotkey(Some(ACTION_MODIFIERS), key_cancel.unwrap(), KeyType::Cancel).unwrap();
+            tmp.register_new_hotkey(Some(ACTION_MODIFIERS), key_record.unwrap(), KeyType::Record).unwrap();
+            tmp.register_new_hotkey(Some(ACTION_MODIFIERS), key_repeat_region.unwrap(), KeyType::RepeatRegion).unwrap();
+            // a wrong preset or profile hotkey must not prevent the application from starting, it is reported in the toasts
+            let mut toasts = Vec::new();
+            if let Err(error) = register_preset_hotkeys(&mut tmp, &startup_settings) {
+                toasts.push(ExportReport { label: "Region preset hotkeys".to_string(), result: Err(error.to_string()), finished: Instant::now() });
+            }
+            if let Err(error) = register_profile_hotkeys(&mut tmp, &startup_settings) {
+                toasts.push(ExportReport { label: "Export profile hotkeys".to_string(), result: Err(error.to_string()), finished: Instant::now() });
+            }
             tmp.set_active_shortcuts(ActiveShortcuts::ScreenshotWaiting).unwrap();
             Self {
                 timer: 0,
@@ -120,23 +226,49 @@ pub mod state_module {
                 tool_color: [0, 0, 0],
                 tool_size: 10.0,
                 settings_dialog: false,
-                settings: Settings::default(),
+                settings_screens: Vec::new(),
+                settings: startup_settings,
                 instant: Instant::now(),
                 starting_point: None,
                 upper_panel_size: Vec2::new(0.0, 0.0),
-                screen_state: 0,
-                screenshot_taken: false,
+                capture_state: CaptureState::Idle,
                 image_converted: false,
                 window_pos: Pos2::new(0.0, 0.0),
                 window_size: Vec2::new(0.0, 0.0),
+                window_maximized: false,
                 crop_screenshot_tmp: Screenshot::new_empty(),
-                saved_to_clipboard_dialog: false,
                 hotkey_manager: tmp,
+                capture_source,
+                region_selection: None,
+                frozen_capture: DynamicImage::new_rgba8(0, 0),
+                frozen_scale_factor: 1.0,
+                selection_texture: None,
+                countdown: None,
+                capture_job: None,
+                hidden_frame: None,
+                quick_job: None,
+                region_job: None,
+                last_region: None,
+                recorder: None,
+                interval_capture: None,
+                capture_mode: CaptureMode::Region,
+                window_dialog: false,
+                file_browser: None,
+                native_dialog: None,
+                overwrite_path: None,
+                export_worker: ExportWorker::new(),
+                toasts,
+                window_list: Vec::new(),
+                pick_windows: Vec::new(),
+                pick_origin: (0, 0),
+                stitch_dialog: false,
+                stitch_folder: String::new(),
+                stitch_use_region: true,
+                scroll_capture: None,
+                stitch_preview: None,
             }
         }
-    }
 
-    impl ScreenshotStr {
         //front
         pub fn toggle_drawing_mode(&mut self, mode: DrawingMode) {
             if self.drawing_mode == Some(mode) {
@@ -160,6 +292,8 @@ pub mod state_module {
             self.image_converted = true;
         }
 
+        /// Maps a window position (in points) to image pixels through the size the image is drawn at,
+        /// so that captures taken at any scale factor are addressed in their own pixels
         pub fn calculate_texture_coordinates(&self, cursor_pos: Pos2, available: Vec2, total_window: Vec2, return_always: bool) -> Option<Pos2> {
             let w = self.screenshot.get_width().unwrap() as f32;
             let h = self.screenshot.get_height().unwrap() as f32;
@@ -443,47 +577,1316 @@ pub mod state_module {
             });
         }
 
-        fn conversion(&mut self) {
-            if Instant::now() > self.instant {
-                self.convert_image();
-                self.instant += Duration::from_millis(5);
+        fn conversion(&mut self) {
+            if Instant::now() > self.instant {
+                self.convert_image();
+                self.instant += Duration::from_millis(5);
+            }
+        }
+
+        /// Quick screenshot: grabs the configured screen after the timer delay and saves it straight
+        /// away in the default format, without showing it
+        pub fn start_quick_screenshot(&mut self, ctx: &Context) {
+            if self.quick_job.is_some() {
+                return;
+            }
+            let settings = read_settings_from_file("settings.json".to_string());
+            let settings = match self.manage_errors(settings) {
+                Some(settings) => settings,
+                None => return,
+            };
+            let format = match self.manage_errors(settings.get_format()) {
+                Some(format) => format,
+                None => return,
+            };
+            let screen = match settings.quick_target {
+                // when the pointer cannot be located the selected screen is used instead
+                QuickTarget::Cursor => self.capture_source.cursor_position()
+                    .and_then(|(x, y)| self.capture_source.display_at(x, y))
+                    .map(|display| display.index)
+                    .unwrap_or(self.screen),
+                QuickTarget::Selected => self.screen,
+            };
+            let repaint_ctx = ctx.clone();
+            let job = CaptureJob::spawn(self.capture_source.clone(), screen, settings.capture_cursor, Duration::from_secs(self.timer as u64), move || repaint_ctx.request_repaint());
+            self.quick_job = Some((job, format, PathBuf::from(settings.path), screen));
+            if self.timer > 0 {
+                // the cancel hotkey can drop the capture while it waits
+                let result = self.hotkey_manager.set_active_shortcuts(ActiveShortcuts::Countdown);
+                self.manage_errors(result);
+            }
+        }
+
+        pub fn check_quick_screenshot(&mut self) {
+            let result = match &self.quick_job {
+                Some((job, _, _, _)) => job.try_result(),
+                None => return,
+            };
+            if let Some(result) = result {
+                let (_, format, path, screen) = self.quick_job.take().unwrap();
+                if self.timer > 0 && self.countdown.is_none() {
+                    self.restore_shortcuts();
+                }
+                if let Some(capture) = self.manage_errors(result) {
+                    println!("Screenshot taken");
+                    let context = name_context(self.capture_source.as_ref(), screen, None);
+                    let template = self.settings.file_name_template.clone();
+                    let options = self.settings.export.clone();
+                    self.queue_export("Quick screenshot", Screenshot::from_capture(capture), move |screenshot| {
+                        let path = screenshot.save_image(&path, &template, &context, format, &options)?;
+                        Ok(format!("saved {}", path.display()))
+                    });
+                }
+            }
+        }
+
+        /// Captures a region on a worker thread: a preset is saved right away with its name in the
+        /// file name, the last region (no preset) is opened in the editor
+        fn start_region_capture(&mut self, ctx: &Context, region: CaptureRegion, preset: Option<String>) {
+            if self.region_job.is_some() {
+                return;
+            }
+            let repaint_ctx = ctx.clone();
+            let job = CaptureJob::spawn_region(self.capture_source.clone(), region, self.settings.capture_cursor, Duration::from_secs(0), move || repaint_ctx.request_repaint());
+            self.region_job = Some((job, region, preset));
+        }
+
+        pub fn capture_preset(&mut self, ctx: &Context, index: usize) {
+            if let Some(preset) = self.settings.presets.get(index).cloned() {
+                self.start_region_capture(ctx, preset.region, Some(preset.name));
+            }
+        }
+
+        pub fn repeat_last_region(&mut self, ctx: &Context) {
+            match self.last_region {
+                Some(region) => self.start_region_capture(ctx, region, None),
+                None => {
+                    self.manage_errors::<()>(Err("no region has been selected yet".into()));
+                }
+            }
+        }
+
+        pub fn check_region_capture(&mut self) {
+            let result = match &self.region_job {
+                Some((job, _, _)) => job.try_result(),
+                None => return,
+            };
+            if let Some(result) = result {
+                let (_, region, preset) = self.region_job.take().unwrap();
+                let screenshot = match self.manage_errors(result) {
+                    Some(capture) => Screenshot::from_capture(capture),
+                    None => return,
+                };
+                match preset {
+                    Some(name) => {
+                        let format = match self.manage_errors(self.settings.get_format()) {
+                            Some(format) => format,
+                            None => return,
+                        };
+                        let context = name_context(self.capture_source.as_ref(), region.screen, Some(name.clone()));
+                        let folder = PathBuf::from(&self.settings.path);
+                        let template = self.settings.file_name_template.clone();
+                        let options = self.settings.export.clone();
+                        self.queue_export(&format!("Region preset {}", name), screenshot, move |screenshot| {
+                            let path = screenshot.save_image(&folder, &template, &context, format, &options)?;
+                            Ok(format!("saved {}", path.display()))
+                        });
+                    }
+                    None => {
+                        self.set_screenshot(screenshot);
+                    }
+                }
+            }
+        }
+
+        /// Stores the last selected region as a new preset and writes the settings file, unless the
+        /// settings dialog is open: its Save button writes the file together with the other changes
+        pub fn add_preset_from_last_region(&mut self) {
+            let region = match self.last_region {
+                Some(region) => region,
+                None => return,
+            };
+            self.settings.presets.push(RegionPreset {
+                name: format!("Region {}", self.settings.presets.len() + 1),
+                region,
+                hotkey: String::new(),
+            });
+            if self.settings_dialog {
+                return;
+            }
+            let result = write_settings_to_file("settings.json".to_string(), &self.settings);
+            self.manage_errors(result);
+        }
+
+        /// Starts recording the selected screen (or the last selected region) or stops the running recording
+        pub fn toggle_recording(&mut self) {
+            if let Some(recorder) = &self.recorder {
+                recorder.stop();
+                return;
+            }
+            let (screen, area) = match (self.settings.record_last_region, self.last_region) {
+                (true, Some(region)) => (region.screen, Some(region.area())),
+                _ => (self.screen, None),
+            };
+            let options = RecordingOptions {
+                screen,
+                area,
+                fps: self.settings.record_fps,
+                max_duration: Duration::from_secs(self.settings.record_seconds as u64),
+                format: self.settings.record_format,
+                with_cursor: self.settings.capture_cursor,
+            };
+            let result = Recorder::start(self.capture_source.clone(), options, PathBuf::from(&self.settings.path));
+            self.recorder = self.manage_errors(result);
+        }
+
+        /// Shows the progress of the running recording and collects its result once encoded
+        pub fn check_recording(&mut self, ctx: &Context) {
+            let recorder = match &self.recorder {
+                Some(recorder) => recorder,
+                None => return,
+            };
+            if recorder.is_finished() {
+                let result = self.recorder.take().unwrap().join();
+                if let Some(path) = self.manage_errors(result) {
+                    self.show_toast("Recording", Ok(format!("saved {}", path.display())));
+                }
+                return;
+            }
+            let stopping = recorder.is_stopping();
+            let elapsed = recorder.elapsed().as_secs_f32();
+            let max_duration = recorder.max_duration().as_secs_f32();
+            Window::new("Recording")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::RIGHT_BOTTOM, Vec2::new(-10.0, -30.0))
+                .show(ctx, |ui| {
+                    if stopping {
+                        ui.label("Encoding...");
+                    } else {
+                        ui.label(format!("\u{23FA} {:.1} / {:.0} s", elapsed, max_duration));
+                        if ui.button("Stop")
+                            .on_hover_text(format!("CTRL + ALT + SHIFT + {}", self.settings.record))
+                            .clicked() {
+                            self.toggle_recording();
+                        }
+                    }
+                });
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        /// Starts capturing the selected screen periodically or stops the running interval capture
+        pub fn toggle_interval_capture(&mut self) {
+            if let Some(interval_capture) = &self.interval_capture {
+                interval_capture.stop();
+                return;
+            }
+            let format = match self.manage_errors(self.settings.get_format()) {
+                Some(format) => format,
+                None => return,
+            };
+            let options = IntervalOptions {
+                screen: self.screen,
+                every: Duration::from_secs(self.settings.interval_seconds.max(1) as u64),
+                limit: self.settings.interval_limit,
+                count: self.settings.interval_count,
+                duration: Duration::from_secs(self.settings.interval_minutes as u64 * 60),
+                skip_identical: self.settings.interval_skip_identical,
+                export_at_1x: self.settings.export_at_1x,
+                with_cursor: self.settings.capture_cursor,
+                format,
+                export: self.settings.export.clone(),
+                folder: PathBuf::from(&self.settings.path),
+            };
+            self.interval_capture = Some(IntervalCapture::start(self.capture_source.clone(), options));
+        }
+
+        /// Shows how many frames the interval capture saved so far and collects its result
+        pub fn check_interval_capture(&mut self, ctx: &Context) {
+            let interval_capture = match &self.interval_capture {
+                Some(interval_capture) => interval_capture,
+                None => return,
+            };
+            if interval_capture.is_finished() {
+                let result = self.interval_capture.take().unwrap().join();
+                if let Some(saved) = self.manage_errors(result) {
+                    self.show_toast("Interval capture", Ok(format!("saved {} screenshots", saved)));
+                }
+                return;
+            }
+            let (saved, skipped) = (interval_capture.saved(), interval_capture.skipped());
+            Window::new("Interval capture")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::LEFT_BOTTOM, Vec2::new(10.0, -30.0))
+                .show(ctx, |ui| {
+                    ui.label(format!("{} saved, {} identical skipped", saved, skipped));
+                    if ui.button("Stop").clicked() {
+                        self.toggle_interval_capture();
+                    }
+                });
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+
+        /// Starts grabbing the last selected region (or the selected screen) while the user scrolls,
+        /// or stops it and stitches the frames; the window is minimized meanwhile
+        pub fn toggle_scroll_capture(&mut self, frame: &mut eframe::Frame) {
+            if let Some(scroll_capture) = self.scroll_capture.take() {
+                frame.set_minimized(false);
+                let shortcuts = if self.show_image { ActiveShortcuts::ScreenshotDone } else { ActiveShortcuts::ScreenshotWaiting };
+                let result = self.hotkey_manager.set_active_shortcuts(shortcuts);
+                self.manage_errors(result);
+                let result = scroll_capture.join();
+                if let Some((frames, scale_factor)) = self.manage_errors(result) {
+                    self.set_stitch_preview(&frames, scale_factor);
+                }
+                return;
+            }
+            let region = if self.stitch_use_region { self.last_region } else { None };
+            self.stitch_preview = None;
+            self.scroll_capture = Some(ScrollCapture::start(self.capture_source.clone(), self.screen, region, Duration::from_millis(300)));
+            // only the cancel shortcut stays active, it stops the capture
+            let result = self.hotkey_manager.set_active_shortcuts(ActiveShortcuts::Countdown);
+            self.manage_errors(result);
+            frame.set_minimized(true);
+        }
+
+        fn set_stitch_preview(&mut self, frames: &[image::RgbaImage], scale_factor: f32) {
+            let result = stitch(frames);
+            if let Some(stitched) = self.manage_errors(result) {
+                self.stitch_preview = Some((stitched, scale_factor, None));
+            }
+        }
+
+        /// Scrolling capture: frames are grabbed while scrolling or loaded from a folder, then stitched
+        /// into one tall image shown with its seams before it replaces the current screenshot
+        pub fn show_stitch_dialog(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
+            // a worker that stopped by itself has failed: joining it restores the window and shows the error
+            if self.scroll_capture.as_ref().map(|capture| capture.is_finished()).unwrap_or(false) {
+                self.toggle_scroll_capture(frame);
+            }
+            let mut toggle = false;
+            let mut load = false;
+            let mut accept = false;
+            let mut discard = false;
+            let mut close = false;
+            Window::new("Scrolling capture")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.set_enabled(!self.error_dialog);
+                    match &self.scroll_capture {
+                        Some(scroll_capture) => {
+                            ui.label(format!("Scroll the page slowly, {} frames grabbed", scroll_capture.frame_count()));
+                            if ui.button("Stop and stitch")
+                                .on_hover_text(format!("CTRL + ALT + SHIFT + {}", self.settings.cancel))
+                                .clicked() {
+                                toggle = true;
+                            }
+                        }
+                        None => {
+                            ui.horizontal(|ui| {
+                                if ui.button("Capture while scrolling").clicked() {
+                                    toggle = true;
+                                }
+                                ui.add_enabled(self.last_region.is_some(), egui::Checkbox::new(&mut self.stitch_use_region, "Only the last selected region"));
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Frames folder");
+                                ui.text_edit_singleline(&mut self.stitch_folder);
+                                if ui.button("Load").clicked() {
+                                    load = true;
+                                }
+                            });
+                        }
+                    }
+                    if let Some((stitched, _, texture)) = &mut self.stitch_preview {
+                        ui.separator();
+                        let unmatched = stitched.seams.iter().filter(|seam| !seam.matched).count();
+                        ui.label(format!("{} x {}, {} seams ({} without overlap), {} identical frames skipped",
+                                         stitched.image.width(), stitched.image.height(), stitched.seams.len(), unmatched, stitched.skipped));
+                        let width = 300.0_f32.min(stitched.image.width() as f32);
+                        let scale = width / stitched.image.width() as f32;
+                        let texture = texture.get_or_insert_with(|| {
+                            // a long page is far taller than a texture may be: upload it at the size it is shown
+                            let image = &stitched.image;
+                            let max_side = ctx.input(|i| i.max_texture_side) as f32;
+                            let texture_scale = (scale * ctx.pixels_per_point()).min(max_side / image.height() as f32).min(1.0);
+                            let preview_width = ((image.width() as f32 * texture_scale).round() as u32).max(1);
+                            let preview_height = ((image.height() as f32 * texture_scale).round() as u32).max(1);
+                            let preview = image::imageops::resize(image, preview_width, preview_height, image::imageops::FilterType::Triangle);
+                            let color_image = ColorImage::from_rgba_unmultiplied([preview.width() as _, preview.height() as _], preview.as_raw());
+                            ctx.load_texture("stitch-preview", color_image, Default::default())
+                        });
+                        let size = Vec2::new(width, stitched.image.height() as f32 * scale);
+                        ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                            let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+                            let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+                            ui.painter().image(texture.id(), rect, uv, Color32::WHITE);
+                            // seams in green, red where the frames were appended without a match
+                            for seam in &stitched.seams {
+                                let y = rect.min.y + seam.y as f32 * scale;
+                                let color = if seam.matched { Color32::GREEN } else { Color32::RED };
+                                ui.painter().hline(rect.x_range(), y, Stroke::new(1.0, color));
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("Use this image").clicked() {
+                                accept = true;
+                            }
+                            if ui.button("Discard").clicked() {
+                                discard = true;
+                            }
+                        });
+                    }
+                    ui.separator();
+                    if ui.add_enabled(self.scroll_capture.is_none(), Button::new("Close")).clicked() {
+                        close = true;
+                    }
+                });
+            if toggle {
+                self.toggle_scroll_capture(frame);
+            }
+            if load {
+                let result = load_frames(&PathBuf::from(&self.stitch_folder));
+                if let Some(frames) = self.manage_errors(result) {
+                    self.set_stitch_preview(&frames, 1.0);
+                }
+            }
+            if accept {
+                if let Some((stitched, scale_factor, _)) = self.stitch_preview.take() {
+                    self.set_screenshot(Screenshot::from_image(DynamicImage::from(stitched.image)).with_scale_factor(scale_factor));
+                    self.stitch_dialog = false;
+                }
+            }
+            if discard {
+                self.stitch_preview = None;
+            }
+            if close {
+                self.stitch_preview = None;
+                self.stitch_dialog = false;
+            }
+            if self.scroll_capture.is_some() {
+                ctx.request_repaint_after(Duration::from_millis(250));
+            }
+        }
+
+        pub fn start_new_screenshot(&mut self, frame: &eframe::Frame) {
+            self.start_capture(frame, CaptureMode::Region);
+        }
+
+        /// Starts a new capture right away, or after the countdown when a timer is set
+        fn start_capture(&mut self, frame: &eframe::Frame, mode: CaptureMode) {
+            self.capture_mode = mode;
+            if self.timer > 0 {
+                self.countdown = Some(Instant::now() + Duration::from_secs(self.timer as u64));
+                let result = self.hotkey_manager.set_active_shortcuts(ActiveShortcuts::Countdown);
+                self.manage_errors(result);
+            } else {
+                self.begin_capture(frame);
+            }
+        }
+
+        fn begin_capture(&mut self, frame: &eframe::Frame) {
+            let window_info = frame.info().window_info;
+            self.window_size = window_info.size;
+            self.window_pos = window_info.position.unwrap_or(self.window_pos);
+            self.window_maximized = window_info.maximized;
+            self.capture_state = CaptureState::Hiding;
+            let result = self.hotkey_manager.set_active_shortcuts(ActiveShortcuts::Pause);
+            self.manage_errors(result);
+        }
+
+        /// Stops the countdown of a delayed capture, or a delayed quick screenshot, before anything is captured
+        pub fn cancel_countdown(&mut self) {
+            self.countdown = None;
+            if let Some((job, _, _, _)) = self.quick_job.take() {
+                job.cancel();
+            }
+            self.restore_shortcuts();
+        }
+
+        fn restore_shortcuts(&mut self) {
+            let shortcuts = if self.show_image { ActiveShortcuts::ScreenshotDone } else { ActiveShortcuts::ScreenshotWaiting };
+            let result = self.hotkey_manager.set_active_shortcuts(shortcuts);
+            self.manage_errors(result);
+        }
+
+        /// Shows the remaining time of a delayed capture and starts the capture when it expires
+        pub fn check_countdown(&mut self, ctx: &Context, frame: &eframe::Frame) {
+            let deadline = match self.countdown {
+                Some(deadline) => deadline,
+                None => return,
+            };
+            if ctx.input(|i| i.key_pressed(Key::Escape)) {
+                self.cancel_countdown();
+                return;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                self.countdown = None;
+                self.begin_capture(frame);
+                ctx.request_repaint();
+                return;
+            }
+            let remaining = deadline - now;
+            Window::new("Delayed capture")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, Vec2::new(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.label(RichText::new(format!("{}", remaining.as_secs() + 1)).size(48.0));
+                        ui.label(format!("Capturing screen in {:.1} seconds", remaining.as_secs_f32()));
+                        if ui.button("Cancel")
+                            .on_hover_text(format!("CTRL + ALT + SHIFT + {}", self.settings.cancel))
+                            .clicked() {
+                            self.cancel_countdown();
+                        }
+                    });
+                });
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        /// Drives the capture session: hide the window (unless it has to be part of the capture),
+        /// let the worker wait for the compositor and capture, select the region, then restore the window
+        pub fn update_capture_session(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
+            match self.capture_state {
+                CaptureState::Idle | CaptureState::Selecting => {}
+                CaptureState::Hiding => {
+                    let settle_time = if self.settings.include_app_window {
+                        Duration::from_secs(0)
+                    } else {
+                        frame.set_visible(false);
+                        self.hidden_frame = Some(ctx.frame_nr());
+                        ctx.request_repaint();
+                        HIDE_TIMEOUT
+                    };
+                    // a window chosen by title can be anywhere on the desktop
+                    let screen = match self.capture_mode {
+                        CaptureMode::Window(_) => ALL_SCREENS,
+                        _ => self.screen,
+                    };
+                    let repaint_ctx = ctx.clone();
+                    self.capture_job = Some(CaptureJob::spawn(self.capture_source.clone(), screen, self.settings.capture_cursor, settle_time, move || repaint_ctx.request_repaint()));
+                    self.capture_state = CaptureState::Capturing;
+                }
+                CaptureState::Capturing => {
+                    if let Some(hidden_frame) = self.hidden_frame {
+                        if ctx.frame_nr() >= hidden_frame + HIDE_FRAMES {
+                            if let Some(job) = &self.capture_job {
+                                job.start();
+                            }
+                            self.hidden_frame = None;
+                        } else {
+                            ctx.request_repaint();
+                        }
+                    }
+                    // no polling: the worker asks for a repaint once the capture is done
+                    let result = match self.capture_job.as_ref().and_then(|job| job.try_result()) {
+                        Some(result) => result,
+                        None => return,
+                    };
+                    self.capture_job = None;
+                    self.hidden_frame = None;
+                    if let CaptureMode::Window(id) = self.capture_mode {
+                        let result = result.and_then(|capture| self.crop_window_capture(&capture, id));
+                        if let Some(screenshot) = self.manage_errors(result) {
+                            self.set_screenshot(screenshot);
+                        }
+                        self.capture_state = CaptureState::Restoring;
+                        return;
+                    }
+                    if let Some(Capture { image, scale_factor }) = self.manage_errors(result) {
+                        // show the frozen capture full screen on its monitor to select a region
+                        self.region_selection = Some(RegionSelection::new(image.width(), image.height()));
+                        self.pick_windows = Vec::new();
+                        if self.capture_mode == CaptureMode::PickWindow {
+                            self.load_pick_windows();
+                        }
+                        self.frozen_capture = image;
+                        self.frozen_scale_factor = scale_factor;
+                        self.selection_texture = None;
+                        if let Ok(monitor) = self.capture_source.display(self.screen) {
+                            frame.set_window_pos(Pos2::new(monitor.x as f32, monitor.y as f32));
+                        }
+                        frame.set_visible(true);
+                        frame.set_decorations(false);
+                        frame.set_fullscreen(true);
+                        frame.set_always_on_top(true);
+                        self.capture_state = CaptureState::Selecting;
+                    } else {
+                        self.capture_state = CaptureState::Restoring;
+                    }
+                }
+                CaptureState::Restoring => {
+                    frame.set_fullscreen(false);
+                    frame.set_decorations(true);
+                    frame.set_always_on_top(false);
+                    frame.set_visible(true);
+                    if self.window_maximized {
+                        frame.set_maximized(true);
+                    } else {
+                        frame.set_window_pos(self.window_pos);
+                        frame.set_window_size(self.window_size);
+                    }
+                    let shortcuts = if self.show_image { ActiveShortcuts::ScreenshotDone } else { ActiveShortcuts::ScreenshotWaiting };
+                    let result = self.hotkey_manager.set_active_shortcuts(shortcuts);
+                    self.manage_errors(result);
+                    self.capture_state = CaptureState::Idle;
+                }
+            }
+        }
+
+        /// Full screen overlay on top of the frozen capture where the user drags the region to keep:
+        /// handles resize it, arrows move it (shift+arrows resize, ctrl for 10px steps),
+        /// enter or double click confirm and escape cancels
+        pub fn show_selection_overlay(&mut self, ctx: &Context) {
+            let mut confirmed = false;
+            let mut whole_screen = false;
+            let mut cancelled = false;
+            let image = &self.frozen_capture;
+            let scale_factor = self.frozen_scale_factor;
+            let (w, h) = (image.width() as f32, image.height() as f32);
+            let texture = self.selection_texture.get_or_insert_with(|| {
+                let color_image = ColorImage::from_rgba_unmultiplied([image.width() as _, image.height() as _], image.to_rgba8().as_bytes());
+                ctx.load_texture("selection-image", color_image, Default::default())
+            }).id();
+
+            CentralPanel::default()
+                .frame(Frame::none().fill(Color32::BLACK))
+                .show(ctx, |ui| {
+                    let screen_rect = ui.max_rect();
+                    // fit the capture in the window keeping its aspect ratio
+                    let scale = (screen_rect.width() / w).min(screen_rect.height() / h);
+                    let image_rect = Rect::from_center_size(screen_rect.center(), Vec2::new(w * scale, h * scale));
+                    let to_image = |p: Pos2| ((p - image_rect.min) / scale).to_pos2();
+                    let to_screen = |p: Pos2| image_rect.min + p.to_vec2() * scale;
+                    let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+                    ui.painter().image(texture, image_rect, uv, Color32::WHITE);
+
+                    let selection = match self.region_selection.as_mut() {
+                        Some(selection) => selection,
+                        None => return,
+                    };
+                    let tolerance = 8.0 / scale;
+                    let response = ui.interact(screen_rect, ui.id().with("region_selection"), Sense::click_and_drag());
+                    if let Some(pos) = response.interact_pointer_pos() {
+                        if response.drag_started() {
+                            selection.begin_drag(to_image(pos), tolerance);
+                        } else if response.dragged() {
+                            selection.drag_to(to_image(pos));
+                        }
+                    }
+                    if response.drag_released() {
+                        selection.end_drag();
+                    }
+                    if response.double_clicked() && selection.rect().is_some() {
+                        confirmed = true;
+                    }
+                    if let Some(pos) = response.hover_pos() {
+                        match selection.handle_at(to_image(pos), tolerance) {
+                            Some(handle) => ctx.set_cursor_icon(handle.cursor_icon()),
+                            None => ctx.set_cursor_icon(CursorIcon::Crosshair),
+                        }
+                    }
+                    ctx.input(|is| {
+                        let step = if is.modifiers.ctrl { 10.0 } else { 1.0 };
+                        let resize = is.modifiers.shift;
+                        if is.key_pressed(Key::ArrowLeft) { selection.nudge(-step, 0.0, resize); }
+                        if is.key_pressed(Key::ArrowRight) { selection.nudge(step, 0.0, resize); }
+                        if is.key_pressed(Key::ArrowUp) { selection.nudge(0.0, -step, resize); }
+                        if is.key_pressed(Key::ArrowDown) { selection.nudge(0.0, step, resize); }
+                        if is.key_pressed(Key::Enter) && selection.rect().is_some() { confirmed = true; }
+                        if is.key_pressed(Key::Escape) { cancelled = true; }
+                    });
+
+                    // dim everything outside of the selection
+                    let painter = ui.painter();
+                    let dim = Color32::from_black_alpha(120);
+                    match selection.rect() {
+                        Some(r) => {
+                            let sr = Rect::from_min_max(to_screen(r.min), to_screen(r.max));
+                            painter.rect_filled(Rect::from_min_max(image_rect.min, Pos2::new(image_rect.max.x, sr.min.y)), 0.0, dim);
+                            painter.rect_filled(Rect::from_min_max(Pos2::new(image_rect.min.x, sr.max.y), image_rect.max), 0.0, dim);
+                            painter.rect_filled(Rect::from_min_max(Pos2::new(image_rect.min.x, sr.min.y), Pos2::new(sr.min.x, sr.max.y)), 0.0, dim);
+                            painter.rect_filled(Rect::from_min_max(Pos2::new(sr.max.x, sr.min.y), Pos2::new(image_rect.max.x, sr.max.y)), 0.0, dim);
+                            painter.rect_stroke(sr, 0.0, Stroke::new(1.0, Color32::WHITE));
+                            for (_, handle_pos) in selection.handles() {
+                                painter.rect_filled(Rect::from_center_size(to_screen(handle_pos), Vec2::splat(7.0)), 0.0, Color32::WHITE);
+                            }
+                            // live size readout above the selection, or inside it when there is no room
+                            if let Some((_, _, width, height)) = selection.crop_area() {
+                                let (pos, anchor) = if sr.min.y > image_rect.min.y + 20.0 {
+                                    (sr.left_top() - Vec2::new(0.0, 4.0), Align2::LEFT_BOTTOM)
+                                } else {
+                                    (sr.left_top() + Vec2::new(4.0, 4.0), Align2::LEFT_TOP)
+                                };
+                                // on HiDPI screens the logical size is shown next to the pixel one
+                                let readout = if (scale_factor - 1.0).abs() > f32::EPSILON {
+                                    let region = CaptureRegion::from_pixels(0, (0, 0, width, height), scale_factor);
+                                    format!("{} x {} px ({} x {} @{}x)", width, height, region.width, region.height, scale_factor)
+                                } else {
+                                    format!("{} x {}", width, height)
+                                };
+                                painter.text(pos, anchor, readout, FontId::proportional(14.0), Color32::WHITE);
+                            }
+                        }
+                        None => {
+                            painter.rect_filled(image_rect, 0.0, dim);
+                        }
+                    }
+                });
+
+            let has_selection = self.region_selection.as_ref().map(|s| s.rect().is_some()).unwrap_or(false);
+            Area::new("selection_toolbar")
+                .anchor(Align2::CENTER_TOP, Vec2::new(0.0, 10.0))
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Drag to select a region");
+                            if ui.add_enabled(has_selection, Button::new("Capture selection")).clicked() {
+                                confirmed = true;
+                            }
+                            if ui.button("Whole screen").clicked() {
+                                whole_screen = true;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                cancelled = true;
+                            }
+                        });
+                    });
+                });
+
+            if confirmed || whole_screen {
+                let image = std::mem::replace(&mut self.frozen_capture, DynamicImage::new_rgba8(0, 0));
+                let area = self.region_selection.as_ref().and_then(|s| s.crop_area());
+                let image = match (confirmed, area) {
+                    (true, Some((x, y, width, height))) => {
+                        if self.screen != ALL_SCREENS {
+                            self.last_region = Some(CaptureRegion::from_pixels(self.screen, (x, y, width, height), self.frozen_scale_factor));
+                        }
+                        // a crop of the frozen capture, what the user saw while selecting; the screen has
+                        // changed since, so it is not grabbed again (repeating the region does capture_area)
+                        image.crop_imm(x, y, width, height)
+                    }
+                    _ => image,
+                };
+                self.set_screenshot(Screenshot::from_image(image).with_scale_factor(self.frozen_scale_factor));
+            }
+            if confirmed || whole_screen || cancelled {
+                self.region_selection = None;
+                self.selection_texture = None;
+                self.frozen_capture = DynamicImage::new_rgba8(0, 0);
+                self.capture_state = CaptureState::Restoring;
+                ctx.request_repaint();
+            }
+        }
+
+        /// Full screen overlay on top of the frozen capture highlighting the window under the pointer:
+        /// a click captures that window and escape cancels
+        pub fn show_window_picker_overlay(&mut self, ctx: &Context) {
+            let mut picked = None;
+            let mut cancelled = false;
+            let image = &self.frozen_capture;
+            let (w, h) = (image.width() as f32, image.height() as f32);
+            let texture = self.selection_texture.get_or_insert_with(|| {
+                let color_image = ColorImage::from_rgba_unmultiplied([image.width() as _, image.height() as _], image.to_rgba8().as_bytes());
+                ctx.load_texture("selection-image", color_image, Default::default())
+            }).id();
+            // read once so that the highlighted area and the crop agree while the checkbox below changes it
+            let decorations = self.settings.window_decorations;
+            let size = (image.width(), image.height(), self.frozen_scale_factor);
+            let areas: Vec<(&WindowInfo, Rect)> = self.pick_windows.iter()
+                .filter_map(|window| {
+                    let (x, y, width, height) = window_pixel_area(window, decorations, self.pick_origin, size)?;
+                    Some((window, Rect::from_min_size(Pos2::new(x as f32, y as f32), Vec2::new(width as f32, height as f32))))
+                })
+                .collect();
+
+            CentralPanel::default()
+                .frame(Frame::none().fill(Color32::BLACK))
+                .show(ctx, |ui| {
+                    let screen_rect = ui.max_rect();
+                    let scale = (screen_rect.width() / w).min(screen_rect.height() / h);
+                    let image_rect = Rect::from_center_size(screen_rect.center(), Vec2::new(w * scale, h * scale));
+                    let to_image = |p: Pos2| ((p - image_rect.min) / scale).to_pos2();
+                    let to_screen = |r: Rect| Rect::from_min_max(image_rect.min + r.min.to_vec2() * scale, image_rect.min + r.max.to_vec2() * scale);
+                    let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+                    ui.painter().image(texture, image_rect, uv, Color32::WHITE);
+
+                    let response = ui.interact(image_rect, ui.id().with("window-picker"), Sense::click());
+                    // the topmost window under the pointer wins
+                    let hovered = response.hover_pos().and_then(|pos| {
+                        let pos = to_image(pos);
+                        areas.iter().rposition(|(_, rect)| rect.contains(pos))
+                    });
+                    let painter = ui.painter();
+                    match hovered {
+                        Some(index) => {
+                            let (window, rect) = &areas[index];
+                            let sr = to_screen(*rect);
+                            painter.rect_filled(sr, 0.0, Color32::from_rgba_unmultiplied(80, 140, 255, 60));
+                            painter.rect_stroke(sr, 0.0, Stroke::new(2.0, Color32::from_rgb(80, 140, 255)));
+                            painter.text(sr.left_top() + Vec2::new(4.0, 4.0), Align2::LEFT_TOP, &window.title, FontId::proportional(14.0), Color32::WHITE);
+                            if response.clicked() {
+                                picked = Some(window.id);
+                            }
+                        }
+                        None => {
+                            painter.rect_filled(image_rect, 0.0, Color32::from_black_alpha(60));
+                        }
+                    }
+                    ctx.set_cursor_icon(CursorIcon::PointingHand);
+                });
+
+            if ctx.input(|i| i.key_pressed(Key::Escape)) {
+                cancelled = true;
+            }
+            Area::new("window-picker-toolbar")
+                .anchor(Align2::CENTER_TOP, Vec2::new(0.0, 12.0))
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Click a window to capture it");
+                            ui.checkbox(&mut self.settings.window_decorations, "Include decorations");
+                            if ui.button("Cancel").clicked() {
+                                cancelled = true;
+                            }
+                        });
+                    });
+                });
+
+            if let Some(id) = picked {
+                let capture = Capture {
+                    image: std::mem::replace(&mut self.frozen_capture, DynamicImage::new_rgba8(0, 0)),
+                    scale_factor: self.frozen_scale_factor,
+                };
+                let result = match self.pick_windows.iter().find(|window| window.id == id) {
+                    Some(window) => crop_to_window(&capture, window, decorations, self.pick_origin),
+                    None => Err("window not found".into()),
+                };
+                if let Some(capture) = self.manage_errors(result) {
+                    self.set_screenshot(Screenshot::from_capture(capture));
+                }
+            }
+            if picked.is_some() || cancelled {
+                self.region_selection = None;
+                self.selection_texture = None;
+                self.pick_windows = Vec::new();
+                self.frozen_capture = DynamicImage::new_rgba8(0, 0);
+                self.capture_state = CaptureState::Restoring;
+                ctx.request_repaint();
+            }
+        }
+
+        /// Lists the visible windows of other applications and the origin of a capture of the selected screen for the picker
+        fn load_pick_windows(&mut self) {
+            let windows = self.capture_source.windows().and_then(|windows| {
+                let origin = capture_origin(self.capture_source.as_ref(), self.screen)?;
+                Ok((windows, origin))
+            });
+            if let Some((windows, origin)) = self.manage_errors(windows) {
+                self.pick_windows = windows.into_iter().filter(|window| window.pid != Some(std::process::id())).collect();
+                self.pick_origin = origin;
+            }
+        }
+
+        /// Crops a capture of the whole desktop to the window with the given id
+        fn crop_window_capture(&self, capture: &Capture, id: u32) -> Result<Screenshot, Box<dyn Error>> {
+            let origin = capture_origin(self.capture_source.as_ref(), ALL_SCREENS)?;
+            let windows = self.capture_source.windows()?;
+            let window = match windows.iter().find(|window| window.id == id) {
+                Some(window) => window,
+                None => return Err("the window is no longer available".into()),
+            };
+            let capture = crop_to_window(capture, window, self.settings.window_decorations, origin)?;
+            Ok(Screenshot::from_capture(capture))
+        }
+
+        /// Lists the windows to capture by title, picking by click or by title starts a capture session
+        pub fn show_window_dialog(&mut self, ctx: &Context, frame: &eframe::Frame) {
+            let mut mode = None;
+            let mut close = false;
+            let mut refresh = false;
+            Window::new("Capture a window")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.set_enabled(!self.error_dialog);
+                    ui.checkbox(&mut self.settings.window_decorations, "Include decorations");
+                    ui.horizontal(|ui| {
+                        if ui.button("Pick by clicking").clicked() {
+                            mode = Some(CaptureMode::PickWindow);
+                        }
+                        if ui.button("Refresh").clicked() {
+                            refresh = true;
+                        }
+                        if ui.button("Close").clicked() {
+                            close = true;
+                        }
+                    });
+                    ui.separator();
+                    if self.window_list.is_empty() {
+                        ui.label("No window found");
+                    }
+                    ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        // topmost windows first
+                        for window in self.window_list.iter().rev() {
+                            let title = if window.title.is_empty() { "(untitled)" } else { window.title.as_str() };
+                            if ui.button(title)
+                                .on_hover_text(format!("{}x{} at ({}, {})", window.width, window.height, window.x, window.y))
+                                .clicked() {
+                                mode = Some(CaptureMode::Window(window.id));
+                            }
+                        }
+                    });
+                });
+            if refresh {
+                self.refresh_window_list();
+            }
+            if let Some(mode) = mode {
+                self.window_dialog = false;
+                self.start_capture(frame, mode);
+            } else if close {
+                self.window_dialog = false;
+            }
+        }
+
+        pub fn refresh_window_list(&mut self) {
+            let result = self.capture_source.windows();
+            let windows = self.manage_errors(result).unwrap_or_default();
+            self.window_list = windows.into_iter().filter(|window| window.pid != Some(std::process::id())).collect();
+        }
+
+        /// Folder proposed by Save As: the most recent one still existing, the save path otherwise
+        fn save_as_folder(&self) -> PathBuf {
+            let folder = self.settings.recent_folders.iter()
+                .map(PathBuf::from)
+                .find(|folder| folder.is_dir())
+                .unwrap_or_else(|| PathBuf::from(&self.settings.path));
+            folder.canonicalize().unwrap_or(folder)
+        }
+
+        /// File name proposed by Save As, from the template without its subfolders
+        fn save_as_file_name(&self) -> String {
+            let mut context = name_context(self.capture_source.as_ref(), self.screen, None);
+            if let Ok(image) = self.screenshot.get_image() {
+                (context.width, context.height) = (image.width(), image.height());
+            }
+            let name = render(&self.settings.file_name_template, &context, 1)
+                .ok()
+                .and_then(|name| name.file_name().map(|name| name.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| String::from("screenshot"));
+            format!("{}.{}", name, self.settings.format)
+        }
+
+        /// Opens the system file dialog, the default format is offered first
+        pub fn save_as_native(&mut self, ctx: &Context) {
+            if self.native_dialog.is_some() {
+                return;
+            }
+            let default = from_extension(&self.settings.format).unwrap_or(OUTPUT_FORMATS[0]);
+            let mut dialog = rfd::AsyncFileDialog::new()
+                .set_title("Save Screenshot")
+                .set_directory(self.save_as_folder())
+                .set_file_name(self.save_as_file_name());
+            for output in std::iter::once(default).chain(OUTPUT_FORMATS.into_iter().filter(|output| *output != default)) {
+                let mut extensions = vec![output.extension];
+                extensions.extend_from_slice(output.aliases);
+                dialog = dialog.add_filter(output.name, &extensions);
+            }
+            dialog = dialog.add_filter("PDF", &["pdf"]);
+            let repaint_ctx = ctx.clone();
+            self.native_dialog = Some(NativeDialog::show(BrowserMode::Save, dialog, move || repaint_ctx.request_repaint()));
+        }
+
+        pub fn open_file_browser(&mut self) {
+            let extensions = OUTPUT_FORMATS.iter()
+                .flat_map(|output| std::iter::once(output.extension).chain(output.aliases.iter().copied()))
+                .chain(std::iter::once("pdf"))
+                .map(String::from)
+                .collect();
+            let result = FileBrowser::new(BrowserMode::Save, &self.save_as_folder(), &self.save_as_file_name(), extensions);
+            self.file_browser = self.manage_errors(result);
+        }
+
+        /// Opens the system file dialog to pick an image to edit
+        pub fn open_image_native(&mut self, ctx: &Context) {
+            if self.native_dialog.is_some() {
+                return;
+            }
+            let dialog = rfd::AsyncFileDialog::new()
+                .set_title("Open Image")
+                .set_directory(self.save_as_folder())
+                .add_filter("Images", &readable_extensions());
+            let repaint_ctx = ctx.clone();
+            self.native_dialog = Some(NativeDialog::show(BrowserMode::Open, dialog, move || repaint_ctx.request_repaint()));
+        }
+
+        /// Acts on the file chosen in the system dialog once it is closed, the in-app browser is
+        /// opened instead when the system dialog could not be shown
+        pub fn check_native_dialog(&mut self) {
+            let result = match &self.native_dialog {
+                Some(dialog) => dialog.try_result(),
+                None => return,
+            };
+            let result = match result {
+                Some(result) => result,
+                None => return,
+            };
+            let mode = self.native_dialog.take().unwrap().mode;
+            match (result, mode) {
+                (DialogResult::Picked(path), BrowserMode::Save) => {
+                    // the system dialog already asked before replacing the file, unless an extension is added
+                    let confirmed = path.extension().is_some();
+                    self.save_as(path, confirmed);
+                }
+                (DialogResult::Picked(path), BrowserMode::Open) => self.open_image(&path),
+                (DialogResult::Cancelled, _) => {}
+                (DialogResult::Unavailable, BrowserMode::Save) => self.open_file_browser(),
+                (DialogResult::Unavailable, BrowserMode::Open) => self.open_image_browser(),
+            }
+        }
+
+        pub fn open_image_browser(&mut self) {
+            let extensions = readable_extensions().into_iter().map(String::from).collect();
+            let result = FileBrowser::new(BrowserMode::Open, &self.save_as_folder(), "", extensions);
+            self.file_browser = self.manage_errors(result);
+        }
+
+        /// Loads the image at path in the editor, in place of the current screenshot
+        pub fn open_image(&mut self, path: &Path) {
+            let result = Screenshot::open(path);
+            if let Some(screenshot) = self.manage_errors(result) {
+                self.file_browser = None;
+                self.set_screenshot(screenshot);
+            }
+        }
+
+        pub fn paste_image(&mut self) {
+            let result = Screenshot::from_clipboard();
+            if let Some(screenshot) = self.manage_errors(result) {
+                self.set_screenshot(screenshot);
+            }
+        }
+
+        /// Opens the first image dropped on the window, a hint is shown while files are dragged over it
+        fn check_dropped_files(&mut self, ctx: &Context) {
+            if self.capture_state != CaptureState::Idle {
+                return;
+            }
+            let (hovering, dropped) = ctx.input(|i| (!i.raw.hovered_files.is_empty(), i.raw.dropped_files.first().cloned()));
+            if hovering {
+                Area::new("drop_hint")
+                    .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                    .show(ctx, |ui| {
+                        Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.label(RichText::new("Drop an image to open it").size(20.0));
+                        });
+                    });
+            }
+            match dropped.map(|file| (file.path, file.bytes)) {
+                Some((Some(path), _)) => self.open_image(&path),
+                // files dropped without a path come with their content
+                Some((None, Some(bytes))) => {
+                    let result = image::load_from_memory(&bytes).map(Screenshot::from_image);
+                    if let Some(screenshot) = self.manage_errors(result.map_err(|e| e.into())) {
+                        self.set_screenshot(screenshot);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        /// Saves the screenshot at path, in the format of its extension (the default format is added
+        /// when there is none), ".pdf" gives a PDF document. An existing file is only replaced once the user confirms
+        pub fn save_as(&mut self, mut path: PathBuf, overwrite: bool) {
+            if path.extension().is_none() {
+                path.set_extension(&self.settings.format);
+            }
+            let extension = path.extension().unwrap_or_default().to_string_lossy().into_owned();
+            let pdf = extension.eq_ignore_ascii_case("pdf");
+            if !pdf && from_extension(&extension).is_none() {
+                self.manage_errors::<()>(Err(format!("unknown image format .{}", extension).into()));
+                return;
+            }
+            if path.exists() && !overwrite {
+                self.overwrite_path = Some(path);
+                return;
+            }
+            self.overwrite_path = None;
+            let label = format!("Save As {}", path.file_name().unwrap_or_default().to_string_lossy());
+            let target = path.clone();
+            if pdf {
+                let (options, export) = (self.settings.pdf.clone(), self.settings.export.clone());
+                self.queue_export(&label, self.screenshot.to_export(), move |screenshot| {
+                    screenshot.save_pdf_as(&target, &options, &export)?;
+                    Ok(format!("saved {}", target.display()))
+                });
+            } else {
+                let context = name_context(self.capture_source.as_ref(), self.screen, None);
+                let options = self.settings.export.clone();
+                self.queue_export(&label, self.screenshot.to_export(), move |screenshot| {
+                    screenshot.save_image_as(&target, &context, &options)?;
+                    Ok(format!("saved {}", target.display()))
+                });
+            }
+            if let Some(folder) = path.parent() {
+                self.settings.add_recent_folder(folder);
+            }
+            let result = write_settings_to_file("settings.json".to_string(), &self.settings);
+            self.manage_errors(result);
+            self.file_browser = None;
+            self.save_dialog = false;
+            self.drawing_mode = self.previous_drawing_mode;
+        }
+
+        /// Copies the screenshot to the clipboard as target, File and Markdown first save it in the default
+        /// folder with the default format
+        pub fn copy_to_clipboard(&mut self, target: ClipboardTarget) {
+            let format = match self.manage_errors(self.settings.get_format()) {
+                Some(format) => format,
+                None => return,
+            };
+            let folder = PathBuf::from(&self.settings.path);
+            let template = self.settings.file_name_template.clone();
+            let context = name_context(self.capture_source.as_ref(), self.screen, None);
+            let options = self.settings.export.clone();
+            self.queue_export(&format!("Copy as {}", target.label()), self.screenshot.to_export(), move |screenshot| {
+                let file = if target.needs_file() {
+                    Some(screenshot.save_image(&folder, &template, &context, format, &options)?)
+                } else {
+                    None
+                };
+                screenshot.save_to_clipboard(target, file.as_deref(), &options)?;
+                Ok(match file {
+                    Some(file) => format!("copied, saved {}", file.display()),
+                    None => String::from("copied to the clipboard"),
+                })
+            });
+        }
+
+        /// Writes the screenshot to every output of the export profile, each output is a job of its own so
+        /// that its success or failure is reported separately
+        pub fn apply_profile(&mut self, index: usize) {
+            let profile = match self.settings.profiles.get(index) {
+                Some(profile) => profile.clone(),
+                None => return,
+            };
+            if !self.show_image {
+                self.manage_errors::<()>(Err("there is no screenshot to export".into()));
+                return;
+            }
+            // the same context gives every output the same name
+            let context = name_context(self.capture_source.as_ref(), self.screen, None);
+            for output in profile.outputs {
+                let folder = PathBuf::from(if output.folder.trim().is_empty() { &self.settings.path } else { output.folder.trim() });
+                let template = if output.template.trim().is_empty() { self.settings.file_name_template.clone() } else { output.template };
+                let format = from_extension(&output.format);
+                let label = format!("{} {}", profile.name, format.map(|format| format.name).unwrap_or(output.format.as_str()));
+                let (context, options) = (context.clone(), output.options);
+                self.queue_export(&label, self.screenshot.to_export(), move |screenshot| {
+                    let format = format.ok_or("unknown image format")?;
+                    let path = screenshot.save_image(&folder, &template, &context, format.format, &options)?;
+                    Ok(format!("saved {}", path.display()))
+                });
+            }
+        }
+
+        /// Size of the screenshot once downscaled to 1x when the settings ask for it, before the export scaling
+        fn export_size(&self) -> (u32, u32) {
+            let (width, height) = (self.screenshot.get_width().unwrap_or(0), self.screenshot.get_height().unwrap_or(0));
+            let scale_factor = self.screenshot.get_scale_factor();
+            if self.settings.export_at_1x && scale_factor > 1.0 {
+                (((width as f32 / scale_factor).round() as u32).max(1), ((height as f32 / scale_factor).round() as u32).max(1))
+            } else {
+                (width, height)
             }
         }
 
-        pub fn check_minimization(&mut self, frame: &mut eframe::Frame) {
-            if self.screenshot_taken {
-                match self.screen_state {
-                    0 => {
-                        frame.set_window_pos(Pos2::new(-200.0, -200.0));
-                        frame.set_window_size(Vec2::new(0.0, 0.0));
-                        if frame.info().window_info.position.unwrap().x == -200.0 && frame.info().window_info.position.unwrap().y == -200.0 {
-                            self.screen_state = 1;
+        /// Saves or copies screenshot on the export worker, downscaled to 1x first when the settings ask for it.
+        /// task returns the message shown once it is done
+        fn queue_export<T>(&self, label: &str, screenshot: Screenshot, task: T)
+            where T: FnOnce(Screenshot) -> Result<String, Box<dyn Error>> + Send + 'static {
+            let at_1x = self.settings.export_at_1x;
+            self.export_worker.submit(label, move || {
+                let screenshot = if at_1x { screenshot.downscaled_to_1x() } else { screenshot };
+                task(screenshot)
+            });
+        }
+
+        /// Adds a message to the toasts of the exports, for results that do not come from the export worker
+        fn show_toast(&mut self, label: &str, result: Result<String, String>) {
+            self.toasts.push(ExportReport { label: label.to_string(), result, finished: Instant::now() });
+        }
+
+        /// Collects the finished exports and shows them in the bottom right corner, with the number of exports
+        /// still running
+        fn show_exports(&mut self, ctx: &Context) {
+            self.toasts.extend(self.export_worker.reports());
+            self.toasts.retain(|toast| toast.finished.elapsed() < if toast.result.is_ok() { TOAST_DURATION } else { 2 * TOAST_DURATION });
+            let pending = self.export_worker.pending();
+            if pending == 0 && self.toasts.is_empty() {
+                return;
+            }
+            Area::new("export_toasts")
+                .anchor(Align2::RIGHT_BOTTOM, [-8.0, -8.0])
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        if pending > 0 {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(format!("Exporting ({} left)", pending));
+                            });
                         }
-                    }
-                    1 => {
-                        let duration = Duration::from_secs(self.timer as u64);
-                        self.screenshot = take_screenshot(duration, self.screen);
-                        self.crop_screenshot_tmp=self.screenshot.clone();
-                        self.starting_point=None;
-                        self.convert_image();
-                        self.show_image = true;
-                        if self.image_converted {
-                            self.screen_state = 2;
+                        for toast in &self.toasts {
+                            match &toast.result {
+                                Ok(message) => ui.label(format!("{}: {}", toast.label, message)),
+                                Err(e) => ui.colored_label(Color32::RED, format!("{} failed: {}", toast.label, e)),
+                            };
                         }
+                    });
+                });
+            // keeps polling the worker and removes the old toasts
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        pub fn show_file_browser(&mut self, ctx: &Context) {
+            let mut browser = match self.file_browser.take() {
+                Some(browser) => browser,
+                None => return,
+            };
+            let mut open = None;
+            let mut up = false;
+            let mut refresh = false;
+            let mut save = false;
+            let mut close = false;
+            let (title, action) = match browser.mode {
+                BrowserMode::Save => ("Save As", "Save"),
+                BrowserMode::Open => ("Open Image", "Open"),
+            };
+            Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.set_enabled(!self.error_dialog && self.overwrite_path.is_none());
+                    ui.horizontal(|ui| {
+                        if ui.button("Up").on_hover_text("Parent folder").clicked() {
+                            up = true;
+                        }
+                        if ui.button("Refresh").clicked() {
+                            refresh = true;
+                        }
+                        ui.label(browser.folder.display().to_string());
+                    });
+                    if !self.settings.recent_folders.is_empty() {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label("Recent");
+                            for folder in &self.settings.recent_folders {
+                                let folder = PathBuf::from(folder);
+                                let name = folder.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| folder.display().to_string());
+                                if ui.button(name).on_hover_text(folder.display().to_string()).clicked() {
+                                    open = Some(folder.clone());
+                                }
+                            }
+                        });
                     }
-                    2 => {
-                        frame.set_window_pos(self.window_pos);
-                        frame.set_window_size(self.window_size);
-                        let result = self.hotkey_manager.set_active_shortcuts(ActiveShortcuts::ScreenshotDone);
-                        self.manage_errors(result);
-                        self.screen_state = 0;
-                        self.screenshot_taken = false;
-                    }
-                    _ => {}
+                    ui.separator();
+                    ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        for folder in &browser.folders {
+                            let name = folder.file_name().unwrap_or_default().to_string_lossy();
+                            if ui.button(format!("\u{1F4C1} {}", name)).clicked() {
+                                open = Some(folder.clone());
+                            }
+                        }
+                        for file in &browser.files {
+                            let name = file.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                            if ui.selectable_label(browser.file_name == name, &name).clicked() {
+                                browser.file_name = name;
+                            }
+                        }
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("File name");
+                        ui.add(TextEdit::singleline(&mut browser.file_name));
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!browser.file_name.trim().is_empty(), Button::new(action)).clicked() {
+                            save = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            let result = if let Some(folder) = open {
+                browser.open(&folder)
+            } else if up {
+                browser.up()
+            } else if refresh {
+                browser.refresh()
+            } else {
+                Ok(())
+            };
+            self.manage_errors(result);
+            if close {
+                return;
+            }
+            let path = browser.path();
+            let mode = browser.mode;
+            self.file_browser = Some(browser);
+            if save {
+                match mode {
+                    BrowserMode::Save => self.save_as(path, false),
+                    BrowserMode::Open => self.open_image(&path),
                 }
             }
         }
 
+        pub fn show_overwrite_dialog(&mut self, ctx: &Context) {
+            let path = match &self.overwrite_path {
+                Some(path) => path.clone(),
+                None => return,
+            };
+            let mut replace = false;
+            let mut cancel = false;
+            Window::new("Replace file")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.set_enabled(!self.error_dialog);
+                    ui.label(format!("{} already exists, do you want to replace it?", path.display()));
+                    ui.horizontal(|ui| {
+                        if ui.button("Replace").clicked() {
+                            replace = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+            if replace {
+                self.save_as(path, true);
+            } else if cancel {
+                self.overwrite_path = None;
+            }
+        }
+
+        /// Opens screenshot in the editor, whatever it comes from
+        fn set_screenshot(&mut self, screenshot: Screenshot) {
+            self.screenshot = screenshot;
+            self.crop_screenshot_tmp = self.screenshot.clone();
+            self.starting_point = None;
+            self.convert_image();
+            self.show_image = true;
+            // editing shortcuts (save, copy, profiles) apply to the new screenshot, a capture session
+            // sets them again when it restores the window
+            let result = self.hotkey_manager.set_active_shortcuts(ActiveShortcuts::ScreenshotDone);
+            self.manage_errors(result);
+        }
+
         pub fn manage_errors<E>(&mut self, result: Result<E, Box<dyn Error>>) -> Option<E> {
             match result {
                 Ok(value) => Some(value),
@@ -499,31 +1902,49 @@ pub mod state_module {
     }
 
     impl App for ScreenshotStr {
+        /// Exports still queued are written before the application quits
+        fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+            self.export_worker.flush();
+        }
+
         fn update(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
             //shortcuts
             if let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
                 //KEY_QUICK
                 if self.hotkey_manager.get_key(KeyType::Quick).is_some() && self.hotkey_manager.get_key(KeyType::Quick).unwrap() == event.id {
-                    println!("Screenshot taken");
-                    let startup_settings = read_settings_from_file("settings.json".to_string());
-                    let startup_settings = self.manage_errors(startup_settings);
-                    if startup_settings.is_some() {
-                        let ss = take_screenshot(Duration::from_secs(0), 0);
-                        let result = ss.save_image(&PathBuf::from(startup_settings.unwrap().path), ImageFormat::Png);
-                        self.manage_errors(result);
-                    }
+                    self.start_quick_screenshot(ctx);
                 }
                 //KEY_SCREENSHOT
                 if self.hotkey_manager.get_key(KeyType::NewScreenshot).is_some() && self.hotkey_manager.get_key(KeyType::NewScreenshot).unwrap() == event.id {
-                    self.window_size = frame.info().window_info.size;
-                    self.window_pos = frame.info().window_info.position.unwrap();
-                    self.screenshot_taken = true;
-                    let result = self.hotkey_manager.set_active_shortcuts(ActiveShortcuts::Pause);
-                    self.manage_errors(result);
+                    self.start_new_screenshot(frame);
+                }
+                //KEY_RECORD
+                if self.hotkey_manager.get_key(KeyType::Record).is_some() && self.hotkey_manager.get_key(KeyType::Record).unwrap() == event.id {
+                    self.toggle_recording();
+                }
+                //KEY_REPEAT_REGION
+                if self.hotkey_manager.get_key(KeyType::RepeatRegion).is_some() && self.hotkey_manager.get_key(KeyType::RepeatRegion).unwrap() == event.id {
+                    self.repeat_last_region(ctx);
+                }
+                //KEY_PRESET
+                if let Some(index) = self.hotkey_manager.find_preset(event.id) {
+                    self.capture_preset(ctx, index);
+                }
+                //KEY_PROFILE
+                if let Some(index) = self.hotkey_manager.find_profile(event.id) {
+                    self.apply_profile(index);
+                }
+                //KEY_CANCEL
+                if self.hotkey_manager.get_key(KeyType::Cancel).is_some() && self.hotkey_manager.get_key(KeyType::Cancel).unwrap() == event.id {
+                    if self.countdown.is_some() || self.quick_job.is_some() {
+                        self.cancel_countdown();
+                    } else if self.scroll_capture.is_some() {
+                        self.toggle_scroll_capture(frame);
+                    }
                 }
                 //KEY_SAVE
                 if self.hotkey_manager.get_key(KeyType::Save).is_some() && self.hotkey_manager.get_key(KeyType::Save).unwrap() == event.id {
-                    if !self.saved_to_clipboard_dialog && !self.settings_dialog && !self.save_dialog {
+                    if !self.settings_dialog && !self.save_dialog {
                         if self.drawing_mode == Some(DrawingMode::Crop) || self.drawing_mode == Some(DrawingMode::Shape){
                             self.screenshot.rollback_changes();
                             self.conversion();
@@ -532,7 +1953,6 @@ pub mod state_module {
                         self.drawing_mode = None;
                     }
                     self.starting_point=None;
-                    self.saved_to_clipboard_dialog = false;
                     self.text_edit_dialog = false;
                     self.settings_dialog = false;
                     self.save_dialog = true;
@@ -557,6 +1977,16 @@ pub mod state_module {
                 }
             }
 
+            // REGION SELECTION OVERLAY
+            if self.capture_state == CaptureState::Selecting {
+                if self.capture_mode == CaptureMode::PickWindow {
+                    self.show_window_picker_overlay(ctx);
+                } else {
+                    self.show_selection_overlay(ctx);
+                }
+                return;
+            }
+
             //DIALOGS
 
             // SAVE_DIALOG
@@ -565,43 +1995,76 @@ pub mod state_module {
                     .collapsible(false)
                     .resizable(false)
                     .show(ctx, |ui| {
-                        ui.set_enabled(!self.error_dialog);
+                        ui.set_enabled(!self.error_dialog && self.file_browser.is_none() && self.overwrite_path.is_none() && self.native_dialog.is_none());
 
                         //close button
                         ui.horizontal(|ui| {
                             ui.label("Save as?");
-                            if ui.button("PNG").clicked() {
-                                self.format = ImageFormat::Png;
-                                //error handling
-                                let result = self.screenshot.save_image(&PathBuf::from(&self.settings.path), self.format);
-                                if self.manage_errors(result).is_none() {
-                                    return;
+                            for output in OUTPUT_FORMATS {
+                                if ui.button(output.name).clicked() {
+                                    self.format = output.format;
+                                    let context = name_context(self.capture_source.as_ref(), self.screen, None);
+                                    let (folder, template, format, options) = (PathBuf::from(&self.settings.path), self.settings.file_name_template.clone(), self.format, self.settings.export.clone());
+                                    self.queue_export(&format!("Save {}", output.name), self.screenshot.to_export(), move |screenshot| {
+                                        let path = screenshot.save_image(&folder, &template, &context, format, &options)?;
+                                        Ok(format!("saved {}", path.display()))
+                                    });
+                                    // the options used are kept for the next saves
+                                    let result = write_settings_to_file("settings.json".to_string(), &self.settings);
+                                    self.manage_errors(result);
+                                    self.save_dialog = false;
+                                    self.drawing_mode = self.previous_drawing_mode;
                                 }
-                                self.save_dialog = false;
-                                self.drawing_mode = self.previous_drawing_mode;
                             }
-                            if ui.button("JPG").clicked() {
-                                self.format = ImageFormat::Jpeg;
-                                let result = self.screenshot.save_image(&PathBuf::from(&self.settings.path), self.format);
-                                if self.manage_errors(result).is_none() {
-                                    return;
-                                }
+                            if ui.button("PDF").clicked() {
+                                let context = name_context(self.capture_source.as_ref(), self.screen, None);
+                                let (folder, template, options) = (PathBuf::from(&self.settings.path), self.settings.file_name_template.clone(), self.settings.pdf.clone());
+                                let export = self.settings.export.clone();
+                                self.queue_export("Save PDF", self.screenshot.to_export(), move |screenshot| {
+                                    let path = screenshot.save_pdf(&folder, &template, &context, &options, &export)?;
+                                    Ok(format!("saved {}", path.display()))
+                                });
+                                let result = write_settings_to_file("settings.json".to_string(), &self.settings);
+                                self.manage_errors(result);
                                 self.save_dialog = false;
                                 self.drawing_mode = self.previous_drawing_mode;
                             }
-                            if ui.button("GIF").clicked() {
-                                self.format = ImageFormat::Gif;
-                                let result = self.screenshot.save_image(&PathBuf::from(&self.settings.path), self.format);
-                                if self.manage_errors(result).is_none() {
-                                    return;
+                        });
+
+                        if !self.settings.profiles.is_empty() {
+                            let mut applied = None;
+                            ui.horizontal_wrapped(|ui| {
+                                ui.label("Profiles");
+                                for (index, profile) in self.settings.profiles.iter().enumerate() {
+                                    let formats: Vec<String> = profile.outputs.iter().map(|output| output.format.to_uppercase()).collect();
+                                    if ui.button(&profile.name).on_hover_text(formats.join(" + ")).clicked() {
+                                        applied = Some(index);
+                                    }
                                 }
+                            });
+                            if let Some(index) = applied {
+                                self.apply_profile(index);
                                 self.save_dialog = false;
                                 self.drawing_mode = self.previous_drawing_mode;
                             }
+                        }
+
+                        let size = self.export_size();
+                        ui.collapsing("Options", |ui| {
+                            export_options_ui(ui, &mut self.settings.export, Some(size));
+                        });
+                        ui.collapsing("PDF options", |ui| {
+                            pdf_options_ui(ui, &mut self.settings.pdf);
                         });
 
                         //close
                         ui.horizontal(|ui| {
+                            if ui.button("Save As...").on_hover_text("Choose the file with the system dialog").clicked() {
+                                self.save_as_native(ctx);
+                            }
+                            if ui.button("Browse...").on_hover_text("Choose the file in this window").clicked() {
+                                self.open_file_browser();
+                            }
                             if ui.button("Cancel").clicked() {
                                 self.drawing_mode = self.previous_drawing_mode;
                                 self.save_dialog = false;
@@ -611,25 +2074,6 @@ pub mod state_module {
             }
 
 
-            if self.saved_to_clipboard_dialog {
-                Window::new("Save Screenshot")
-                    .collapsible(false)
-                    .title_bar(false)
-                    .resizable(false)
-                    .movable(false)
-                    .show(ctx, |ui| {
-                        ui.set_enabled(!self.error_dialog);
-
-                        if ui.label("Saved to clipboard!").clicked_elsewhere() {
-                            self.saved_to_clipboard_dialog = false;
-                        };
-                        if ui.button("Ok").clicked() {
-                            self.saved_to_clipboard_dialog = false;
-                            self.drawing_mode = self.previous_drawing_mode;
-                        }
-                    });
-            }
-
             // SETTING_DIALOG
             if self.settings_dialog {
                 Window::new("Settings")
@@ -674,11 +2118,148 @@ pub mod state_module {
                                 .char_limit(1)
                                 .desired_width(ui.available_width() / 4.0));
                         });
+                        ui.horizontal(|ui| {
+                            ui.label("Cancel delayed capture");
+                            ui.label("CTRL + ALT + SHIFT + ");
+                            ui.add(TextEdit::singleline(&mut self.settings.cancel)
+                                .char_limit(1)
+                                .desired_width(ui.available_width() / 4.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Start/stop recording");
+                            ui.label("CTRL + ALT + SHIFT + ");
+                            ui.add(TextEdit::singleline(&mut self.settings.record)
+                                .char_limit(1)
+                                .desired_width(ui.available_width() / 4.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Repeat last region");
+                            ui.label("CTRL + ALT + SHIFT + ");
+                            ui.add(TextEdit::singleline(&mut self.settings.repeat_region)
+                                .char_limit(1)
+                                .desired_width(ui.available_width() / 4.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Quick Screenshot of");
+                            ComboBox::from_id_source("quick_target")
+                                .selected_text(match self.settings.quick_target {
+                                    QuickTarget::Cursor => "Screen under the mouse",
+                                    QuickTarget::Selected => "Selected screen",
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.settings.quick_target, QuickTarget::Cursor, "Screen under the mouse");
+                                    ui.selectable_value(&mut self.settings.quick_target, QuickTarget::Selected, "Selected screen");
+                                });
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Default format");
+                            for output in OUTPUT_FORMATS {
+                                ui.selectable_value(&mut self.settings.format, output.extension.to_string(), output.name);
+                            }
+                        });
+                        ui.collapsing("Encoder options", |ui| {
+                            export_options_ui(ui, &mut self.settings.export, None);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Copy to clipboard as");
+                            for target in ClipboardTarget::ALL {
+                                ui.selectable_value(&mut self.settings.clipboard_target, target, target.label());
+                            }
+                        });
+                        ui.checkbox(&mut self.settings.include_app_window, "Include this window in captures");
+                        ui.checkbox(&mut self.settings.capture_cursor, "Include the mouse pointer in captures");
+                        ui.checkbox(&mut self.settings.export_at_1x, "Save HiDPI captures at their logical size (1x)");
+                        ui.horizontal(|ui| {
+                            ui.label("Recording");
+                            ui.add(DragValue::new(&mut self.settings.record_fps).clamp_range(1..=30).suffix(" fps"));
+                            ui.add(DragValue::new(&mut self.settings.record_seconds).clamp_range(1..=120).suffix(" s max"));
+                            ui.selectable_value(&mut self.settings.record_format, AnimationFormat::Gif, "GIF");
+                            ui.selectable_value(&mut self.settings.record_format, AnimationFormat::Apng, "APNG");
+                        });
+                        ui.checkbox(&mut self.settings.record_last_region, "Record the last selected region");
+                        ui.horizontal(|ui| {
+                            ui.label("Interval capture every");
+                            ui.add(DragValue::new(&mut self.settings.interval_seconds).clamp_range(1..=3600).suffix(" s"));
+                            ui.selectable_value(&mut self.settings.interval_limit, IntervalLimit::Count, "Stop after")
+                                .on_hover_text("Identical frames that are skipped do not count");
+                            ui.add(DragValue::new(&mut self.settings.interval_count).clamp_range(1..=100000).suffix(" saved shots"));
+                            ui.selectable_value(&mut self.settings.interval_limit, IntervalLimit::Duration, "Run for");
+                            ui.add(DragValue::new(&mut self.settings.interval_minutes).clamp_range(1..=1440).suffix(" min"));
+                        });
+                        ui.checkbox(&mut self.settings.interval_skip_identical, "Skip frames identical to the previous one");
+                        ui.label("Region presets");
+                        let mut removed = None;
+                        for (index, preset) in self.settings.presets.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.add(TextEdit::singleline(&mut preset.name).desired_width(120.0));
+                                ui.label("CTRL + SHIFT + ");
+                                ui.add(TextEdit::singleline(&mut preset.hotkey)
+                                    .char_limit(1)
+                                    .desired_width(20.0));
+                                ui.label(format!("screen {}, {}x{} at ({}, {})", preset.region.screen, preset.region.width, preset.region.height, preset.region.x, preset.region.y));
+                                if ui.button("\u{1F5D1}").on_hover_text("Delete").clicked() {
+                                    removed = Some(index);
+                                }
+                            });
+                        }
+                        if let Some(index) = removed {
+                            self.settings.presets.remove(index);
+                        }
+                        if ui.add_enabled(self.last_region.is_some(), Button::new("Add the last selected region")).clicked() {
+                            self.add_preset_from_last_region();
+                        }
+                        ui.label("Export profiles");
+                        let mut removed = None;
+                        for (index, profile) in self.settings.profiles.iter_mut().enumerate() {
+                            ui.push_id(("profile", index), |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.add(TextEdit::singleline(&mut profile.name).desired_width(120.0));
+                                    ui.label("CTRL + ALT + ");
+                                    ui.add(TextEdit::singleline(&mut profile.hotkey)
+                                        .char_limit(1)
+                                        .desired_width(20.0));
+                                    if ui.button("\u{1F5D1}").on_hover_text("Delete").clicked() {
+                                        removed = Some(index);
+                                    }
+                                });
+                                ui.collapsing(format!("Outputs ({})", profile.outputs.len()), |ui| {
+                                    profile_outputs_ui(ui, &mut profile.outputs);
+                                });
+                            });
+                        }
+                        if let Some(index) = removed {
+                            self.settings.profiles.remove(index);
+                        }
+                        if ui.button("Add a profile").on_hover_text("PNG and JPG in the default folder, to be edited").clicked() {
+                            self.settings.profiles.push(ExportProfile {
+                                name: format!("Profile {}", self.settings.profiles.len() + 1),
+                                ..ExportProfile::default()
+                            });
+                        }
                         ui.horizontal(|ui| {
                             ui.label("Path");
                             //turn pathbuf into string
                             ui.add(TextEdit::singleline(&mut self.settings.path));
                         });
+                        ui.horizontal(|ui| {
+                            ui.label("File name");
+                            ui.add(TextEdit::singleline(&mut self.settings.file_name_template))
+                                .on_hover_text("{date:%Y-%m-%d} {time} {screen} {width} {height} {counter} {counter:3} {preset}, '/' creates subfolders");
+                        });
+                        // preview with the current screenshot, or the selected screen when there is none
+                        let screen = self.settings_screens.iter().find(|monitor| monitor.index == self.screen);
+                        let mut context = NameContext::new(screen.map(|monitor| monitor.index), None);
+                        context.screen_size = screen.map(Monitor::physical_size);
+                        if self.show_image {
+                            context.width = self.screenshot.get_width().unwrap_or(0);
+                            context.height = self.screenshot.get_height().unwrap_or(0);
+                        } else if let Some(screen) = screen {
+                            (context.width, context.height) = screen.physical_size();
+                        }
+                        match render(&self.settings.file_name_template, &context, 1) {
+                            Ok(name) => ui.label(format!("Preview: {}.{}", name.display(), self.settings.format)),
+                            Err(error) => ui.colored_label(Color32::RED, error.to_string()),
+                        };
                         //close
                         ui.horizontal(|ui| {
                             if ui.button("Cancel").clicked() {
@@ -740,6 +2321,49 @@ pub mod state_module {
                                 if self.manage_errors(result).is_none() {
                                     return;
                                 }
+                                //KEY_CANCEL
+                                let key_cancel = startup_settings.get_cancel_hotkey();
+                                let result = self.manage_errors(key_cancel);
+                                if result.is_none() {
+                                    return;
+                                }
+                                let key_cancel = result.unwrap();
+                                let result = self.hotkey_manager.register_new_hotkey(Some(ACTION_MODIFIERS), key_cancel, KeyType::Cancel);
+                                if self.manage_errors(result).is_none() {
+                                    return;
+                                }
+                                //KEY_RECORD
+                                let key_record = startup_settings.get_record_hotkey();
+                                let result = self.manage_errors(key_record);
+                                if result.is_none() {
+                                    return;
+                                }
+                                let key_record = result.unwrap();
+                                let result = self.hotkey_manager.register_new_hotkey(Some(ACTION_MODIFIERS), key_record, KeyType::Record);
+                                if self.manage_errors(result).is_none() {
+                                    return;
+                                }
+                                //KEY_REPEAT_REGION
+                                let key_repeat_region = startup_settings.get_repeat_region_hotkey();
+                                let result = self.manage_errors(key_repeat_region);
+                                if result.is_none() {
+                                    return;
+                                }
+                                let key_repeat_region = result.unwrap();
+                                let result = self.hotkey_manager.register_new_hotkey(Some(ACTION_MODIFIERS), key_repeat_region, KeyType::RepeatRegion);
+                                if self.manage_errors(result).is_none() {
+                                    return;
+                                }
+                                //KEY_PRESET
+                                let result = register_preset_hotkeys(&mut self.hotkey_manager, &startup_settings);
+                                if self.manage_errors(result).is_none() {
+                                    return;
+                                }
+                                //KEY_PROFILE
+                                let result = register_profile_hotkeys(&mut self.hotkey_manager, &startup_settings);
+                                if self.manage_errors(result).is_none() {
+                                    return;
+                                }
                                 self.drawing_mode = self.previous_drawing_mode;
                                 self.settings_dialog = false;
                             }
@@ -762,7 +2386,27 @@ pub mod state_module {
                         });
                     });
             }
-            self.check_minimization(frame);
+            if self.window_dialog {
+                self.show_window_dialog(ctx, frame);
+            }
+            self.check_native_dialog();
+            if self.file_browser.is_some() {
+                self.show_file_browser(ctx);
+            }
+            if self.overwrite_path.is_some() {
+                self.show_overwrite_dialog(ctx);
+            }
+            if self.stitch_dialog {
+                self.show_stitch_dialog(ctx, frame);
+            }
+            self.check_countdown(ctx, frame);
+            self.check_quick_screenshot();
+            self.check_region_capture();
+            self.check_recording(ctx);
+            self.check_interval_capture(ctx);
+            self.update_capture_session(ctx, frame);
+            self.check_dropped_files(ctx);
+            self.show_exports(ctx);
 
             // header of the app
             TopBottomPanel::top("header").frame(
@@ -774,93 +2418,159 @@ pub mod state_module {
                 }).show(ctx, |ui| {
                 ui.set_enabled(!self.error_dialog);
 
-                let timer = self.timer;
                 let screen = self.screen;
 
-                let timer_str = format!("{} Seconds", timer);
-                let screen_str = format!("Screen {}", screen);
+                let screen_str = if screen == ALL_SCREENS { "All screens".to_string() } else { format!("Screen {}", screen) };
                 self.upper_panel_size = ui.available_size();
 
                 ui.horizontal(|ui| {
                     if ui.button("New Screenshot")
                         .on_hover_text(format!("CTRL + {}", self.settings.new_screenshot))
-                        .clicked() {
-                        self.window_size = frame.info().window_info.size;
-                        self.window_pos = frame.info().window_info.position.unwrap();
-                        self.screenshot_taken = true;
-                        let result = self.hotkey_manager.set_active_shortcuts(ActiveShortcuts::Pause);
-                        self.manage_errors(result);
+                        .clicked() && self.countdown.is_none() && self.capture_state == CaptureState::Idle {
+                        self.start_new_screenshot(frame);
                     }
 
                     ui.separator();
 
-                    // combo box timer for the screenshot
-                    ComboBox::from_label("Timer")
-                        .selected_text(timer_str)
-                        .show_ui(ui, |ui| {
-                            if ui.selectable_value(&mut 0, 0, "No Timer").clicked() {
-                                self.timer = 0;
-                            }
-                            if ui.selectable_value(&mut 3, 3, "3 Seconds").clicked() {
-                                self.timer = 3;
-                            }
-                            if ui.selectable_value(&mut 5, 5, "5 Seconds").clicked() {
-                                self.timer = 5;
-                            }
-                            if ui.selectable_value(&mut 10, 10, "10 Seconds").clicked() {
-                                self.timer = 10;
-                            }
-                        });
+                    // delay in seconds before the screenshot
+                    ui.add(DragValue::new(&mut self.timer)
+                        .clamp_range(0..=3600)
+                        .speed(0.2)
+                        .suffix(" s"));
+                    ui.label("Timer");
 
                     // combo box screen for the screenshot
                     ComboBox::from_label("Screen")
                         .selected_text(screen_str)
                         .show_ui(ui, |ui| {
-                            let screens = get_screens();
+                            let screens = get_screens(self.capture_source.as_ref());
                             for (index, screen) in screens.iter().enumerate() {
-                                if ui.selectable_value(&mut self.screen, index, &format!("Screen {} ({}x{})", index, screen.display_info.height, screen.display_info.width)).clicked() {
+                                if ui.selectable_value(&mut self.screen, index, &format!("Screen {} ({}x{} @{}x)", index, screen.width, screen.height, screen.scale_factor)).clicked() {
                                     self.screen = index;
                                 }
                             }
+                            if screens.len() > 1 {
+                                ui.selectable_value(&mut self.screen, ALL_SCREENS, "All screens");
+                            }
                         });
 
+                    // record button
+                    let record_icon = if self.recorder.is_some() { "\u{23F9}" } else { "\u{23FA}" };
+                    if ui.button(record_icon)
+                        .on_hover_text(format!("CTRL + ALT + SHIFT + {}", self.settings.record))
+                        .clicked() && self.capture_state == CaptureState::Idle {
+                        self.toggle_recording();
+                    }
+
+                    // repeat last region button
+                    if ui.add_enabled(self.last_region.is_some(), Button::new("\u{27F2}"))
+                        .on_hover_text(format!("Repeat last region (CTRL + ALT + SHIFT + {})", self.settings.repeat_region))
+                        .clicked() && self.capture_state == CaptureState::Idle {
+                        self.repeat_last_region(ctx);
+                    }
+
+                    // region presets menu
+                    ui.menu_button("\u{1F4CC}", |ui| {
+                        let mut clicked = None;
+                        for (index, preset) in self.settings.presets.iter().enumerate() {
+                            let label = if preset.hotkey.is_empty() {
+                                preset.name.clone()
+                            } else {
+                                format!("{} (CTRL + SHIFT + {})", preset.name, preset.hotkey)
+                            };
+                            if ui.button(label).clicked() {
+                                clicked = Some(index);
+                            }
+                        }
+                        if self.settings.presets.is_empty() {
+                            ui.label("No region presets");
+                        }
+                        ui.separator();
+                        if ui.add_enabled(self.last_region.is_some(), Button::new("Save last region as preset")).clicked() {
+                            self.add_preset_from_last_region();
+                            ui.close_menu();
+                        }
+                        if let Some(index) = clicked {
+                            self.capture_preset(ctx, index);
+                            ui.close_menu();
+                        }
+                    }).response.on_hover_text("Region presets");
+
+                    // window capture button
+                    if ui.button("\u{1F5D7}")
+                        .on_hover_text("Capture a window")
+                        .clicked() && self.capture_state == CaptureState::Idle && self.countdown.is_none() {
+                        self.window_dialog = !self.window_dialog;
+                        if self.window_dialog {
+                            self.refresh_window_list();
+                        }
+                    }
+
+                    // scrolling capture button
+                    if ui.button("\u{1F4DC}")
+                        .on_hover_text("Scrolling capture")
+                        .clicked() && self.capture_state == CaptureState::Idle && self.scroll_capture.is_none() {
+                        self.stitch_dialog = !self.stitch_dialog;
+                    }
+
+                    // interval capture button
+                    if ui.button("\u{23F1}")
+                        .on_hover_text("Interval capture")
+                        .clicked() && self.capture_state == CaptureState::Idle {
+                        self.toggle_interval_capture();
+                    }
+
+                    // open menu, the image replaces the current screenshot in the editor
+                    ui.menu_button("\u{1F4C2}", |ui| {
+                        if ui.button("Open...").clicked() {
+                            self.open_image_native(ctx);
+                            ui.close_menu();
+                        }
+                        if ui.button("Browse...").on_hover_text("Choose the file in this window").clicked() {
+                            self.open_image_browser();
+                            ui.close_menu();
+                        }
+                        if ui.button("Paste image").clicked() {
+                            self.paste_image();
+                            ui.close_menu();
+                        }
+                    }).response.on_hover_text("Open an image or drop one on the window");
+
                     ui.separator();
 
                     // save button
                     if ui.button("\u{1F4BE}")
                         .on_hover_text(format!("CTRL + {}", self.settings.save))
                         .clicked() {
-                        if !self.saved_to_clipboard_dialog && !self.settings_dialog && !self.save_dialog {
+                        if !self.settings_dialog && !self.save_dialog {
                             self.previous_drawing_mode = self.drawing_mode;
                             self.drawing_mode = None;
                         }
                         self.settings_dialog = false;
-                        self.saved_to_clipboard_dialog = false;
                         self.text_edit_dialog = false;
                         self.save_dialog = true;
                     }
 
-                    // save to clipboard button
-                    if ui.button("\u{1F4CB}").clicked() {
-                        let flag = !self.saved_to_clipboard_dialog && !self.settings_dialog && !self.save_dialog;
-                        self.settings_dialog = false;
-                        self.save_dialog = false;
-                        self.text_edit_dialog = false;
-                        let result = self.screenshot.save_to_clipboard();
-                        if self.manage_errors(result).is_none() {
-                            return;
-                        }
-                        if flag {
-                            self.previous_drawing_mode = self.drawing_mode;
-                            self.drawing_mode = None;
+                    // save to clipboard button, copies as the default target, the menu next to it picks another one
+                    let mut copy = None;
+                    if ui.button("\u{1F4CB}").on_hover_text(format!("Copy as {}", self.settings.clipboard_target.label())).clicked() {
+                        copy = Some(self.settings.clipboard_target);
+                    }
+                    ui.menu_button("Copy as...", |ui| {
+                        for target in ClipboardTarget::ALL {
+                            if ui.button(target.label()).clicked() {
+                                copy = Some(target);
+                                ui.close_menu();
+                            }
                         }
-                        self.saved_to_clipboard_dialog = true;
+                    });
+                    if let Some(target) = copy {
+                        self.copy_to_clipboard(target);
                     }
                     // settings button in the top right corner
                     ui.with_layout(Layout::top_down(Align::RIGHT), |ui| {
                         if ui.button("\u{2699}").clicked() {
-                            let flag = !self.saved_to_clipboard_dialog && !self.settings_dialog && !self.save_dialog;
-                            self.saved_to_clipboard_dialog = false;
+                            let flag = !self.settings_dialog && !self.save_dialog;
                             self.save_dialog = false;
                             self.text_edit_dialog = false;
                             let result = read_settings_from_file("settings.json".to_string());
@@ -873,6 +2583,7 @@ pub mod state_module {
                                 self.previous_drawing_mode = self.drawing_mode;
                                 self.drawing_mode = None;
                             }
+                            self.settings_screens = get_screens(self.capture_source.as_ref());
                             self.settings_dialog = true;
                         }
                     });
@@ -1221,17 +2932,171 @@ pub mod state_module {
         }
     }
 
-    pub fn get_screens() -> Vec<Screen> {
-        let screens = Screen::all().unwrap();
-        screens
+    /// Binds CTRL + SHIFT + key to every region preset that has a hotkey
+    pub fn register_preset_hotkeys(manager: &mut HotkeyManager, settings: &Settings) -> Result<(), Box<dyn Error>> {
+        manager.clear_presets()?;
+        for (index, preset) in settings.presets.iter().enumerate() {
+            if let Some(code) = preset.get_hotkey()? {
+                manager.register_new_hotkey(Some(Modifiers::CONTROL | Modifiers::SHIFT), code, KeyType::Preset(index))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Binds CTRL + ALT + key to every export profile that has a hotkey
+    pub fn register_profile_hotkeys(manager: &mut HotkeyManager, settings: &Settings) -> Result<(), Box<dyn Error>> {
+        manager.clear_profiles()?;
+        for (index, profile) in settings.profiles.iter().enumerate() {
+            if let Some(code) = profile.get_hotkey()? {
+                manager.register_new_hotkey(Some(Modifiers::CONTROL | Modifiers::ALT), code, KeyType::Profile(index))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Widgets editing the encoder options, shared by the save dialog and the settings
+    /// size is the one of the image to export, to preview the scaled size
+    pub fn export_options_ui(ui: &mut egui::Ui, options: &mut ExportOptions, size: Option<(u32, u32)>) {
+        ui.horizontal(|ui| {
+            ui.label("JPG quality");
+            ui.add(Slider::new(&mut options.jpeg_quality, 1..=100));
+        });
+        ui.horizontal(|ui| {
+            ui.label("PNG compression");
+            ui.selectable_value(&mut options.png_compression, PngCompression::Fast, "Fast");
+            ui.selectable_value(&mut options.png_compression, PngCompression::Default, "Default");
+            ui.selectable_value(&mut options.png_compression, PngCompression::Best, "Best");
+        });
+        ui.horizontal(|ui| {
+            ui.label("PNG filter");
+            ComboBox::from_id_source("png_filter")
+                .selected_text(format!("{:?}", options.png_filter))
+                .show_ui(ui, |ui| {
+                    for filter in [PngFilter::NoFilter, PngFilter::Sub, PngFilter::Up, PngFilter::Avg, PngFilter::Paeth, PngFilter::Adaptive] {
+                        ui.selectable_value(&mut options.png_filter, filter, format!("{:?}", filter));
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("GIF colors");
+            ui.add(DragValue::new(&mut options.gif_colors).clamp_range(2..=256));
+            ui.checkbox(&mut options.gif_dither, "Dithering");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Metadata");
+            ui.selectable_value(&mut options.metadata, MetadataMode::Embed, "Embed")
+                .on_hover_text("Capture time, screen and note inside PNG and JPG files");
+            ui.selectable_value(&mut options.metadata, MetadataMode::Sidecar, "JSON sidecar");
+            ui.selectable_value(&mut options.metadata, MetadataMode::Strip, "Strip all");
+        });
+        ui.add_enabled_ui(options.metadata != MetadataMode::Strip, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Note");
+                ui.add(TextEdit::singleline(&mut options.note).desired_width(160.0));
+            });
+        });
+        scale_options_ui(ui, &mut options.scale, size);
+    }
+
+    /// Widgets editing the size of the exported image
+    pub fn scale_options_ui(ui: &mut egui::Ui, options: &mut ScaleOptions, size: Option<(u32, u32)>) {
+        ui.horizontal(|ui| {
+            ui.label("Size");
+            ui.selectable_value(&mut options.mode, ScaleMode::Original, "Original");
+            ui.selectable_value(&mut options.mode, ScaleMode::Percent, "Percent");
+            ui.selectable_value(&mut options.mode, ScaleMode::MaxSize, "Max size");
+            ui.selectable_value(&mut options.mode, ScaleMode::ExactSize, "Exact size");
+        });
+        ui.horizontal(|ui| {
+            match options.mode {
+                ScaleMode::Original => {}
+                ScaleMode::Percent => {
+                    ui.add(DragValue::new(&mut options.percent).clamp_range(1..=100).suffix(" %"));
+                }
+                ScaleMode::MaxSize => {
+                    ui.add(DragValue::new(&mut options.width).clamp_range(0..=20000).prefix("width ").suffix(" px"))
+                        .on_hover_text("0 for no limit");
+                    ui.add(DragValue::new(&mut options.height).clamp_range(0..=20000).prefix("height ").suffix(" px"))
+                        .on_hover_text("0 for no limit");
+                }
+                ScaleMode::ExactSize => {
+                    ui.add(DragValue::new(&mut options.width).clamp_range(1..=20000).prefix("width ").suffix(" px"));
+                    ui.add_enabled(!options.keep_aspect, DragValue::new(&mut options.height).clamp_range(1..=20000).prefix("height ").suffix(" px"));
+                    ui.checkbox(&mut options.keep_aspect, "Keep aspect ratio");
+                }
+            }
+            if options.mode != ScaleMode::Original {
+                ui.selectable_value(&mut options.filter, ScaleFilter::Nearest, "Nearest")
+                    .on_hover_text("Hard pixel edges, for pixel art");
+                ui.selectable_value(&mut options.filter, ScaleFilter::Lanczos3, "Lanczos3")
+                    .on_hover_text("Smooth, for photos and text");
+            }
+        });
+        if let Some((width, height)) = size {
+            let (scaled_width, scaled_height) = options.target_size(width, height);
+            ui.label(format!("Exported at {}x{} ({}x{} on screen)", scaled_width, scaled_height, width, height));
+        }
+    }
+
+    /// Widgets editing the outputs of an export profile, empty folder and template fields use the defaults
+    pub fn profile_outputs_ui(ui: &mut egui::Ui, outputs: &mut Vec<ProfileOutput>) {
+        let mut removed = None;
+        for (index, output) in outputs.iter_mut().enumerate() {
+            ui.push_id(index, |ui| {
+                ui.horizontal(|ui| {
+                    for format in OUTPUT_FORMATS {
+                        ui.selectable_value(&mut output.format, format.extension.to_string(), format.name);
+                    }
+                    if ui.button("\u{1F5D1}").on_hover_text("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Folder");
+                    ui.add(TextEdit::singleline(&mut output.folder).hint_text("default").desired_width(120.0));
+                    ui.label("Name");
+                    ui.add(TextEdit::singleline(&mut output.template).hint_text("default").desired_width(160.0));
+                });
+                ui.collapsing("Options", |ui| {
+                    export_options_ui(ui, &mut output.options, None);
+                });
+                ui.separator();
+            });
+        }
+        if let Some(index) = removed {
+            outputs.remove(index);
+        }
+        if ui.button("Add an output").clicked() {
+            outputs.push(ProfileOutput::default());
+        }
+    }
+
+    /// Widgets editing the PDF export options
+    pub fn pdf_options_ui(ui: &mut egui::Ui, options: &mut PdfOptions) {
+        ui.horizontal(|ui| {
+            ui.label("Page");
+            ui.selectable_value(&mut options.page_size, PageSize::A4, "A4");
+            ui.selectable_value(&mut options.page_size, PageSize::Letter, "Letter");
+            ui.selectable_value(&mut options.page_size, PageSize::Image, "Image size");
+            ui.add_enabled(options.page_size != PageSize::Image, egui::Checkbox::new(&mut options.landscape, "Landscape"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Resolution");
+            ui.add(DragValue::new(&mut options.dpi).clamp_range(36..=1200).suffix(" dpi"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Layout");
+            ui.selectable_value(&mut options.layout, PdfLayout::FitToPage, "Fit to page");
+            ui.selectable_value(&mut options.layout, PdfLayout::SplitPages, "Split tall captures");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Title");
+            ui.add(TextEdit::singleline(&mut options.title).desired_width(160.0));
+            ui.checkbox(&mut options.timestamp, "Timestamp");
+        });
     }
 
-    pub fn take_screenshot(timer: Duration, screen: usize) -> Screenshot {
-        let screens = Screen::all().unwrap();
-        let screen = screens[screen].clone();
-        //screenshot after delay
-        let ss1 = Screenshot::screenshot_after_delay(timer, screen).unwrap();
-        //save image to clipboard
-        ss1
+    pub fn get_screens(source: &dyn CaptureSource) -> Vec<Monitor> {
+        source.displays().unwrap_or_default()
     }
-}
\ No newline at end of file
+}
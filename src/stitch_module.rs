@@ -0,0 +1,272 @@
+#![allow(dead_code, clippy::module_inception)]
+pub mod stitch_module {
+    use std::collections::hash_map::DefaultHasher;
+    use std::error::Error;
+    use std::hash::{Hash, Hasher};
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+    use image::{imageops, RgbaImage};
+    use thiserror::Error;
+    use crate::capture_module::capture_module::{capture_region, capture_screen, CaptureRegion, CaptureSource};
+
+    /// Overlaps smaller than this are not trusted, a few matching rows are too easy to find by chance
+    const MIN_OVERLAP: u32 = 16;
+    /// Fraction of the overlapping rows that must be identical
+    const MATCH_THRESHOLD: f32 = 0.95;
+
+    #[derive(Error, Debug)]
+    enum StitchError {
+        #[error("no frame to stitch")]
+        NoFrames,
+        #[error("frames have different widths ({0} and {1})")]
+        WidthMismatch(u32, u32),
+        #[error("no png or jpg image found in {0}")]
+        NoImages(String),
+    }
+
+    /// Where two consecutive frames were joined in the stitched image
+    #[derive(Debug, Clone, Copy)]
+    pub struct Seam {
+        /// first row of the stitched image taken from the newer frame
+        pub y: u32,
+        /// rows shared by the two frames
+        pub overlap: u32,
+        /// false when no overlap was found and the frames were simply appended
+        pub matched: bool,
+    }
+
+    pub struct Stitched {
+        pub image: RgbaImage,
+        pub seams: Vec<Seam>,
+        /// frames dropped because they were identical to the previous one
+        pub skipped: usize,
+    }
+
+    fn row_hashes(image: &RgbaImage) -> Vec<(u64, bool)> {
+        let row_len = image.width() as usize * 4;
+        image.as_raw().chunks(row_len.max(1)).map(|row| {
+            let mut hasher = DefaultHasher::new();
+            row.hash(&mut hasher);
+            // rows of a single color (margins, blank lines) match everywhere and say nothing about the scroll
+            let uniform = row.chunks(4).all(|pixel| pixel == &row[0..4]);
+            (hasher.finish(), uniform)
+        }).collect()
+    }
+
+    /// Number of rows at the bottom of previous that are repeated at the top of next, found by matching
+    /// row hashes; the best scoring overlap wins, the largest one on ties
+    pub fn find_overlap(previous: &RgbaImage, next: &RgbaImage) -> Option<u32> {
+        let previous_rows = row_hashes(previous);
+        let next_rows = row_hashes(next);
+        let max_overlap = previous_rows.len().min(next_rows.len());
+        let mut best: Option<(f32, u32)> = None;
+        for overlap in (MIN_OVERLAP as usize..=max_overlap).rev() {
+            let bottom = &previous_rows[previous_rows.len() - overlap..];
+            let top = &next_rows[..overlap];
+            let mut informative = 0;
+            let mut matching = 0;
+            for (a, b) in bottom.iter().zip(top) {
+                if a.1 && b.1 {
+                    continue;
+                }
+                informative += 1;
+                if a.0 == b.0 {
+                    matching += 1;
+                }
+            }
+            if informative < MIN_OVERLAP as usize / 2 {
+                continue;
+            }
+            let score = matching as f32 / informative as f32;
+            if score >= MATCH_THRESHOLD && best.map(|(s, _)| score > s).unwrap_or(true) {
+                best = Some((score, overlap as u32));
+                if score >= 1.0 {
+                    break;
+                }
+            }
+        }
+        best.map(|(_, overlap)| overlap)
+    }
+
+    /// Assembles overlapping frames of the same region, top to bottom, into one tall image
+    pub fn stitch(frames: &[RgbaImage]) -> Result<Stitched, Box<dyn Error>> {
+        let first = match frames.first() {
+            Some(first) => first,
+            None => return Err(Box::new(StitchError::NoFrames)),
+        };
+        let width = first.width();
+        let mut parts: Vec<(&RgbaImage, u32)> = vec![(first, 0)];
+        let mut seams = Vec::new();
+        let mut skipped = 0;
+        let mut height = first.height();
+        for frame in &frames[1..] {
+            if frame.width() != width {
+                return Err(Box::new(StitchError::WidthMismatch(width, frame.width())));
+            }
+            let previous = parts.last().unwrap().0;
+            if frame.as_raw() == previous.as_raw() {
+                skipped += 1;
+                continue;
+            }
+            let overlap = find_overlap(previous, frame);
+            let overlap_rows = overlap.unwrap_or(0);
+            if overlap_rows >= frame.height() {
+                skipped += 1;
+                continue;
+            }
+            seams.push(Seam { y: height, overlap: overlap_rows, matched: overlap.is_some() });
+            height += frame.height() - overlap_rows;
+            parts.push((frame, overlap_rows));
+        }
+
+        let mut image = RgbaImage::new(width, height);
+        let mut y = 0;
+        for (frame, overlap) in parts {
+            let rows = frame.height() - overlap;
+            let part = imageops::crop_imm(frame, 0, overlap, width, rows).to_image();
+            imageops::replace(&mut image, &part, 0, y as i64);
+            y += rows;
+        }
+        Ok(Stitched { image, seams, skipped })
+    }
+
+    /// Reads the png and jpg files of a folder sorted by name, to be stitched in that order
+    pub fn load_frames(folder: &Path) -> Result<Vec<RgbaImage>, Box<dyn Error>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(folder)? {
+            let file = entry?.path();
+            let extension = file.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+            if file.is_file() && ["png", "jpg", "jpeg"].contains(&extension.as_str()) {
+                files.push(file);
+            }
+        }
+        if files.is_empty() {
+            return Err(Box::new(StitchError::NoImages(folder.display().to_string())));
+        }
+        files.sort();
+        let mut frames = Vec::new();
+        for file in files {
+            frames.push(image::open(file)?.to_rgba8());
+        }
+        Ok(frames)
+    }
+
+    /// Grabs the same screen or region over and over on a worker thread while the user scrolls,
+    /// frames identical to the previous one are not kept
+    pub struct ScrollCapture {
+        stop: Arc<AtomicBool>,
+        frames: Arc<Mutex<Vec<RgbaImage>>>,
+        scale_factor: Arc<Mutex<f32>>,
+        handle: JoinHandle<Result<(), String>>,
+    }
+
+    impl ScrollCapture {
+        pub fn start(source: Arc<dyn CaptureSource>, screen: usize, region: Option<CaptureRegion>, every: Duration) -> ScrollCapture {
+            let stop = Arc::new(AtomicBool::new(false));
+            let frames = Arc::new(Mutex::new(Vec::new()));
+            let scale_factor = Arc::new(Mutex::new(1.0));
+            let (stop_worker, frames_worker, scale_worker) = (stop.clone(), frames.clone(), scale_factor.clone());
+            let handle = thread::spawn(move || {
+                while !stop_worker.load(Ordering::SeqCst) {
+                    let started = Instant::now();
+                    let frame = grab_frame(source.as_ref(), screen, region).map_err(|e| e.to_string())?;
+                    *scale_worker.lock().unwrap() = frame.1;
+                    let mut frames = frames_worker.lock().unwrap();
+                    if frames.last().map(|last: &RgbaImage| last.as_raw() != frame.0.as_raw()).unwrap_or(true) {
+                        frames.push(frame.0);
+                    }
+                    drop(frames);
+                    thread::sleep(every.saturating_sub(started.elapsed()));
+                }
+                Ok(())
+            });
+            ScrollCapture { stop, frames, scale_factor, handle }
+        }
+
+        pub fn stop(&self) {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+
+        pub fn frame_count(&self) -> usize {
+            self.frames.lock().unwrap().len()
+        }
+
+        pub fn is_finished(&self) -> bool {
+            self.handle.is_finished()
+        }
+
+        /// Waits for the worker and returns the distinct frames with the scale factor they were taken at
+        pub fn join(self) -> Result<(Vec<RgbaImage>, f32), Box<dyn Error>> {
+            self.stop();
+            match self.handle.join() {
+                Ok(result) => result?,
+                Err(_) => return Err("scrolling capture worker panicked".into()),
+            }
+            let frames = std::mem::take(&mut *self.frames.lock().unwrap());
+            let scale_factor = *self.scale_factor.lock().unwrap();
+            Ok((frames, scale_factor))
+        }
+    }
+
+    fn grab_frame(source: &dyn CaptureSource, screen: usize, region: Option<CaptureRegion>) -> Result<(RgbaImage, f32), Box<dyn Error>> {
+        match region {
+            Some(region) => {
+                let capture = capture_region(source, &region, false)?;
+                Ok((capture.image.to_rgba8(), capture.scale_factor))
+            }
+            None => {
+                let capture = capture_screen(source, screen, false)?;
+                Ok((capture.image.to_rgba8(), capture.scale_factor))
+            }
+        }
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use image::Rgba;
+
+        /// Tall image where every row is different, as a long scrolled page
+        fn page(width: u32, height: u32) -> RgbaImage {
+            RgbaImage::from_fn(width, height, |x, y| Rgba([(y % 251) as u8, (y / 251) as u8, (x * 7 + y) as u8, 255]))
+        }
+
+        fn window(page: &RgbaImage, top: u32, height: u32) -> RgbaImage {
+            imageops::crop_imm(page, 0, top, page.width(), height).to_image()
+        }
+
+        #[test]
+        fn overlapping_frames_rebuild_the_page() {
+            let page = page(20, 300);
+            let frames = vec![window(&page, 0, 100), window(&page, 60, 100), window(&page, 130, 100), window(&page, 200, 100)];
+            let stitched = stitch(&frames).unwrap();
+            assert_eq!(stitched.image.dimensions(), (20, 300));
+            assert_eq!(stitched.image.as_raw(), page.as_raw());
+            assert_eq!(stitched.seams.iter().map(|seam| (seam.y, seam.overlap, seam.matched)).collect::<Vec<_>>(),
+                       vec![(100, 40, true), (160, 30, true), (230, 30, true)]);
+            assert_eq!(stitched.skipped, 0);
+        }
+
+        #[test]
+        fn repeated_and_unrelated_frames() {
+            let page = page(20, 300);
+            let first = window(&page, 0, 100);
+            let unrelated = RgbaImage::from_fn(20, 100, |x, y| Rgba([255 - y as u8, x as u8, 99, 255]));
+            let stitched = stitch(&[first.clone(), first.clone(), unrelated]).unwrap();
+            assert_eq!(stitched.skipped, 1);
+            assert_eq!(stitched.image.height(), 200);
+            assert!(!stitched.seams[0].matched);
+            assert_eq!(find_overlap(&first, &window(&page, 100 - MIN_OVERLAP / 2, 100)), None);
+        }
+
+        #[test]
+        fn frames_must_match() {
+            assert!(stitch(&[]).is_err());
+            assert!(stitch(&[RgbaImage::new(10, 10), RgbaImage::new(12, 10)]).is_err());
+        }
+    }
+}